@@ -0,0 +1,76 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use repair_json::Builder;
+
+mod fixtures;
+
+/// Chunk sizes to stream the input in, from a byte at a time up to a typical network read.
+const CHUNK_SIZES: [usize; 4] = [1, 5, 64, 4096];
+
+fn inputs() -> Vec<(&'static str, String)> {
+	vec![
+		("tool_call", fixtures::TOOL_CALL.to_string()),
+		("large_array", fixtures::large_array(2_000)),
+	]
+}
+
+fn update(c: &mut Criterion) {
+	let mut group = c.benchmark_group("builder/update");
+
+	for (name, input) in inputs() {
+		group.throughput(Throughput::Bytes(input.len() as u64));
+
+		for chunk_size in CHUNK_SIZES {
+			let id = BenchmarkId::new(name, chunk_size);
+			group.bench_with_input(id, &input, |b, input| {
+				b.iter(|| {
+					let mut builder = Builder::new();
+					for chunk in input.as_bytes().chunks(chunk_size) {
+						builder.update(&chunk).unwrap();
+					}
+					builder
+				});
+			});
+		}
+	}
+
+	group.finish();
+}
+
+/// Takes a completed snapshot after every chunk, the way streaming UIs render partial responses.
+fn snapshots(c: &mut Criterion) {
+	let mut group = c.benchmark_group("builder/snapshots");
+
+	for (name, input) in inputs() {
+		group.throughput(Throughput::Bytes(input.len() as u64));
+
+		for chunk_size in &CHUNK_SIZES[1..] {
+			let id = BenchmarkId::new(format!("{name}/view"), chunk_size);
+			group.bench_with_input(id, &input, |b, input| {
+				b.iter(|| {
+					let mut builder = Builder::new();
+					for chunk in input.as_bytes().chunks(*chunk_size) {
+						builder.update(&chunk).unwrap();
+						let (prefix, suffix) = builder.snapshot().unwrap();
+						black_box((prefix, suffix));
+					}
+				});
+			});
+
+			let id = BenchmarkId::new(format!("{name}/owned"), chunk_size);
+			group.bench_with_input(id, &input, |b, input| {
+				b.iter(|| {
+					let mut builder = Builder::new();
+					for chunk in input.as_bytes().chunks(*chunk_size) {
+						builder.update(&chunk).unwrap();
+						black_box(builder.clone().completed_bytes().unwrap());
+					}
+				});
+			});
+		}
+	}
+
+	group.finish();
+}
+
+criterion_group!(benches, update, snapshots);
+criterion_main!(benches);
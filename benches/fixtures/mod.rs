@@ -0,0 +1,69 @@
+//! Inputs shared by the benchmarks.
+#![allow(dead_code)]
+
+/// A realistic tool call, as streamed back by an LLM.
+pub const TOOL_CALL: &str = include_str!("tool_call.json");
+
+/// A tool call whose arguments are mostly made of long string values, grown to at least `size` bytes.
+pub fn llm_output(size: usize) -> String {
+	let paragraph = "The quick brown fox jumps over the lazy dog, and then writes a \\\"quoted\\\" note about it. ";
+	let accented = "Ça coûte 12€ — très cher! 🦀 ";
+
+	let mut output =
+		String::from(r#"{"name": "write_file", "arguments": {"path": "notes.md", "chunks": ["#);
+	while output.len() < size {
+		output.push('"');
+		for _ in 0..32 {
+			output.push_str(paragraph);
+		}
+		output.push_str(accented);
+		output.push_str("\", ");
+	}
+	output.push_str("\"\"]}}");
+
+	output
+}
+
+/// An array of `count` small records, like a paginated API response or a table extracted by an LLM.
+pub fn large_array(count: usize) -> String {
+	let mut output = String::from("[");
+	for i in 0..count {
+		if i > 0 {
+			output.push_str(", ");
+		}
+		output.push_str(&format!(
+			r#"{{"id": {i}, "name": "item-{i}", "price": {}.{:02}, "in_stock": {}, "tags": ["a", "b"], "parent": null}}"#,
+			i * 7 % 1000,
+			i % 100,
+			i % 3 == 0
+		));
+	}
+	output.push(']');
+
+	output
+}
+
+/// `depth` arrays and objects nested inside each other.
+pub fn nested(depth: usize) -> String {
+	let mut output = String::new();
+	for i in 0..depth {
+		output.push_str(if i % 2 == 0 { r#"{"a": "# } else { "[1, " });
+	}
+	output.push_str("true");
+	for i in (0..depth).rev() {
+		output.push(if i % 2 == 0 { '}' } else { ']' });
+	}
+
+	output
+}
+
+/// Cuts `input` in the middle of its last string value, so there is something left to repair.
+pub fn truncated(input: &str) -> &str {
+	let cut = input.len() * 9 / 10;
+	let cut = (0..=cut)
+		.rev()
+		.find(|i| input.is_char_boundary(*i))
+		.unwrap_or(0);
+
+	&input[..cut]
+}
@@ -0,0 +1,43 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use repair_json::repair;
+
+mod fixtures;
+
+fn inputs() -> Vec<(&'static str, String)> {
+	vec![
+		("tool_call", fixtures::TOOL_CALL.to_string()),
+		("large_array", fixtures::large_array(10_000)),
+		("nested", fixtures::nested(1_000)),
+	]
+}
+
+fn complete(c: &mut Criterion) {
+	let mut group = c.benchmark_group("repair/complete");
+
+	for (name, input) in inputs() {
+		group.throughput(Throughput::Bytes(input.len() as u64));
+		group.bench_with_input(BenchmarkId::from_parameter(name), &input, |b, input| {
+			b.iter(|| repair(input.as_str()).unwrap());
+		});
+	}
+
+	group.finish();
+}
+
+fn truncated(c: &mut Criterion) {
+	let mut group = c.benchmark_group("repair/truncated");
+
+	for (name, input) in inputs() {
+		let input = fixtures::truncated(&input);
+
+		group.throughput(Throughput::Bytes(input.len() as u64));
+		group.bench_with_input(BenchmarkId::from_parameter(name), input, |b, input| {
+			b.iter(|| repair(input).unwrap());
+		});
+	}
+
+	group.finish();
+}
+
+criterion_group!(benches, complete, truncated);
+criterion_main!(benches);
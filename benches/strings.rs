@@ -0,0 +1,40 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use repair_json::Builder;
+
+mod fixtures;
+
+fn strings(c: &mut Criterion) {
+	let mut group = c.benchmark_group("strings");
+
+	for size in [1 << 20, 4 << 20] {
+		let input = fixtures::llm_output(size);
+		group.throughput(Throughput::Bytes(input.len() as u64));
+
+		group.bench_with_input(BenchmarkId::new("update", size), &input, |b, input| {
+			b.iter(|| {
+				let mut builder = Builder::with_capacity(input.len());
+				builder.update(&input.as_str()).unwrap();
+				builder
+			});
+		});
+
+		group.bench_with_input(
+			BenchmarkId::new("update_streamed", size),
+			&input,
+			|b, input| {
+				b.iter(|| {
+					let mut builder = Builder::with_capacity(input.len());
+					for chunk in input.as_bytes().chunks(16) {
+						builder.update(&chunk).unwrap();
+					}
+					builder
+				});
+			},
+		);
+	}
+
+	group.finish();
+}
+
+criterion_group!(benches, strings);
+criterion_main!(benches);
@@ -1,27 +1,28 @@
-use crate::{verifier::Verifier, Error, Status};
+use alloc::{string::String, vec, vec::Vec};
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::io::IoSlice;
 
-/// Expanded options for constructing a `Builder` instance.
-#[derive(Debug)]
-pub struct Options {
-	pub maximum_depth: usize,
-	pub initial_capacity: usize,
-}
+use crate::{
+	format::Formatter,
+	patch::{Patch, Value},
+	report::RepairReport,
+	translate::{Output, Translator},
+	verifier::{Completion, Verifier},
+	ConfigError, Dialect, Error, ErrorKind, OnError, Options, RollbackError, Status,
+};
 
-impl Default for Options {
-	fn default() -> Self {
-		Self {
-			initial_capacity: 512,
-			maximum_depth: std::usize::MAX,
-		}
-	}
-}
+/// The UTF-8 encoding of U+FEFF, which some encoders put at the start of their output.
+const BOM: &[u8] = b"\xEF\xBB\xBF";
 
 /// A string builder for JSON that can repair and complete incomplete/damaged JSON.
 ///
 /// # Remarks
 ///
-/// Unlike the [`Verifier`], adding a sequence of characters that would make the underlying JSON object
-/// invalid will cause the `Builder` to remain invalid, even if more characters are added to it later.
+/// Unlike the [`Verifier`], what happens when a character would make the underlying JSON object invalid depends on
+/// the [`OnError`] mode in its [`Options`]: by default ([`OnError::Poison`]) the `Builder` remains invalid, even if
+/// more characters are added to it later. With [`OnError::Stop`], the rest of that update is dropped but later ones
+/// are still applied, and with [`OnError::Skip`] only the offending character is dropped.
 ///
 /// # Example
 /// ```
@@ -44,23 +45,74 @@ impl Default for Options {
 ///         "mother": null}}
 /// "#.trim().to_string()));
 /// ```
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Builder {
 	pub(crate) data: Vec<u8>,
 	pub(crate) invalid: bool,
 	pub(crate) verifier: Verifier,
+	pub(crate) on_error: OnError,
+	pub(crate) consumed: usize,
+	/// How much of a byte order mark has been skipped at the start of the input.
+	pub(crate) bom: usize,
+	/// Translates the input into strict JSON, unless it already is.
+	pub(crate) translator: Option<Translator>,
+	/// The offsets of the bytes dropped because of [`OnError::Skip`], with adjacent ranges merged.
+	pub(crate) skipped: Vec<Range<usize>>,
+	pub(crate) patched: Option<Value>,
+	pub(crate) suffix: Vec<u8>,
+	pub(crate) history: History,
+}
+
+impl Default for Builder {
+	fn default() -> Self {
+		Self {
+			data: vec![],
+			invalid: false,
+			verifier: Verifier::default(),
+			on_error: OnError::default(),
+			consumed: 0,
+			bom: 0,
+			translator: None,
+			skipped: vec![],
+			patched: None,
+			suffix: vec![],
+			history: History::new(),
+		}
+	}
+}
+
+// a clone diverges from the original, so it gets its own history that only shares the checkpoints taken before it.
+impl Clone for Builder {
+	fn clone(&self) -> Self {
+		Self {
+			data: self.data.clone(),
+			invalid: self.invalid,
+			verifier: self.verifier.clone(),
+			on_error: self.on_error,
+			consumed: self.consumed,
+			bom: self.bom,
+			translator: self.translator.clone(),
+			skipped: self.skipped.clone(),
+			patched: self.patched.clone(),
+			suffix: self.suffix.clone(),
+			history: self.history.fork(),
+		}
+	}
 }
 
 impl Builder {
-	/// Creates a new `Builder` with the default maximum depth of [`std::usize::MAX`].
+	/// Creates a new `Builder` with the default maximum depth of [`usize::MAX`].
 	#[must_use]
 	pub fn new() -> Self {
 		Self::default()
 	}
 
 	/// Creates a new [`Builder`] with the specified maximum depth.
-	#[must_use]
-	pub fn with_maximum_depth(maximum_depth: usize) -> Self {
+	///
+	/// # Errors
+	///
+	/// Returns an error if `maximum_depth` is `0`.
+	pub fn with_maximum_depth(maximum_depth: usize) -> Result<Self, ConfigError> {
 		Self::with_options(&Options {
 			maximum_depth,
 			..Default::default()
@@ -70,31 +122,52 @@ impl Builder {
 	/// Creates a new `Builder` with the specified initial capacity.
 	#[must_use]
 	pub fn with_capacity(initial_capacity: usize) -> Self {
-		Self::with_options(&Options {
+		let options = Options {
 			initial_capacity,
 			..Default::default()
-		})
+		};
+
+		Self::from_parts(&options, Verifier::default())
 	}
 
 	/// Creates a new `Builder` with the specified options.
-	#[must_use]
-	pub fn with_options(options: &Options) -> Self {
+	///
+	/// # Errors
+	///
+	/// Returns an error if the options are invalid, as described in [`Options::validate`].
+	pub fn with_options(options: &Options) -> Result<Self, ConfigError> {
+		Ok(Self::from_parts(options, Verifier::with_options(options)?))
+	}
+
+	fn from_parts(options: &Options, verifier: Verifier) -> Self {
 		Self {
+			verifier,
+			bom: 0,
+			consumed: 0,
+			translator: if options.dialect == Dialect::Json {
+				None
+			} else {
+				Some(Translator::new(options.dialect))
+			},
 			invalid: false,
+			skipped: vec![],
+			patched: None,
+			suffix: vec![],
+			history: History::new(),
+			on_error: options.on_error,
 			data: Vec::with_capacity(options.initial_capacity),
-			verifier: Verifier::with_maximum_depth(options.maximum_depth),
 		}
 	}
 
 	/// Returns the current length of this JSON object.
 	#[must_use]
-	pub fn len(&self) -> usize {
+	pub const fn len(&self) -> usize {
 		self.data.len()
 	}
 
 	/// Returns `true` if this JSON object is empty.
 	#[must_use]
-	pub fn is_empty(&self) -> bool {
+	pub const fn is_empty(&self) -> bool {
 		self.data.is_empty()
 	}
 
@@ -104,45 +177,253 @@ impl Builder {
 		self.verifier.status()
 	}
 
+	/// Returns the offsets (relative to all input provided since the last reset, including any that was dropped because
+	/// of [`OnError::Stop`]) of the bytes that were dropped because of [`OnError::Skip`], as ranges of consecutive
+	/// bytes in increasing order.
+	#[must_use]
+	pub fn skipped(&self) -> &[Range<usize>] {
+		&self.skipped
+	}
+
 	/// Resets this JSON object to its initial state.
 	pub fn reset(&mut self) {
+		self.bom = 0;
+		self.consumed = 0;
 		self.invalid = false;
 
+		self.patched = None;
+		self.history = History::new();
 		self.data.clear();
+		if let Some(translator) = &mut self.translator {
+			translator.reset();
+		}
+		self.skipped.clear();
 		self.verifier.reset();
 	}
 
+	/// Captures the current state of this JSON object, so it can later be restored with [`Builder::rollback`].
+	///
+	/// # Example
+	/// ```
+	/// # use repair_json::Builder;
+	/// let mut builder = Builder::new();
+	/// builder.update(&r#"{ "name": "#).unwrap();
+	///
+	/// let checkpoint = builder.checkpoint();
+	/// assert!(builder.update(&"}").is_err());
+	///
+	/// builder.rollback(checkpoint).unwrap();
+	/// builder.update(&r#""miguel" }"#).unwrap();
+	///
+	/// assert_eq!(builder.string(), Ok(r#"{ "name": "miguel" }"#.to_string()));
+	/// ```
+	#[must_use]
+	pub fn checkpoint(&self) -> Checkpoint {
+		Checkpoint {
+			id: self.history.id,
+			stamp: stamp(),
+			epoch: self.history.epoch,
+			length: self.data.len(),
+			invalid: self.invalid,
+			bom: self.bom,
+			consumed: self.consumed,
+			translator: self.translator.clone(),
+			skipped: self.skipped.len(),
+			verifier: self.verifier.clone(),
+		}
+	}
+
+	/// Restores this JSON object to the state it was in when `checkpoint` was taken, discarding anything
+	/// appended since then. This also clears the invalid state if the builder was valid at the time.
+	///
+	/// # Remarks
+	///
+	/// A checkpoint can be restored any number of times, as long as nothing it captured has been discarded since: the
+	/// builder must not have been [reset](Builder::reset), or rolled back to an earlier state, in between.
+	///
+	/// # Errors
+	///
+	/// Returns [`RollbackError::Foreign`] if `checkpoint` was taken from another `Builder` (including a clone of this
+	/// one, or the builder this one was cloned from after the clone was made) or before a reset, and
+	/// [`RollbackError::Stale`] if an earlier rollback discarded part of it. The builder is left unchanged either way.
+	pub fn rollback(&mut self, checkpoint: Checkpoint) -> Result<(), RollbackError> {
+		self.history.check(&checkpoint)?;
+
+		self.history.truncate(checkpoint.length);
+
+		self.invalid = checkpoint.invalid;
+		self.bom = checkpoint.bom;
+		self.translator = checkpoint.translator;
+		self.consumed = checkpoint.consumed;
+		self.verifier = checkpoint.verifier;
+		self.data.truncate(checkpoint.length);
+		self.skipped.truncate(checkpoint.skipped);
+		// the last range may have grown since, but only with bytes from after the checkpoint.
+		if let Some(last) = self.skipped.last_mut() {
+			last.end = last.end.min(checkpoint.consumed);
+		}
+
+		Ok(())
+	}
+
 	/// Appends the provided source to this JSON object.
 	///
 	/// # Remarks
 	///
-	/// Unlike the [`Verifier`], adding a sequence of characters that would make the underlying JSON object
-	/// invalid will cause the `Builder` to remain invalid, even if more characters are added to it later.
+	/// What happens when a character would make the underlying JSON object invalid depends on the
+	/// [`OnError`] mode the `Builder` was created with. By default ([`OnError::Poison`]), the `Builder`
+	/// will remain invalid, even if more characters are added to it later.
+	///
+	/// A UTF-8 byte order mark at the start of the input is skipped. Input in another [`Dialect`] is translated into
+	/// strict JSON as it's received, so everything else (including [`Builder::len`] and error contexts) refers to
+	/// the translated JSON, while error offsets still refer to the input.
 	///
 	/// # Errors
 	///
-	/// Returns an error if adding the provided source would cause this JSON object to become invalid, or if
-	/// this JSON object is already invalid.
+	/// Returns an error if adding the provided source would cause this JSON object to become invalid (unless
+	/// using [`OnError::Skip`]), or if this JSON object is already invalid. Errors for rejected bytes describe where
+	/// they happened.
+	///
+	/// # Example
+	/// ```
+	/// # use repair_json::{Builder, ErrorKind};
+	/// let mut builder = Builder::new();
+	/// let error = builder.update(&r#"{ "tags": ["a", "b"} }"#).unwrap_err();
+	///
+	/// assert_eq!(error.kind(), ErrorKind::Invalid);
+	/// assert_eq!(error.offset(), Some(19));
+	/// assert_eq!(error.path(), Some("/tags/1"));
+	/// assert_eq!(error.to_string(), r#"The input stream is not valid JSON. (at byte 19, path /tags/1)
+	///   { "tags": ["a", "b"}
+	///                      ^"#);
+	/// ```
 	pub fn update(&mut self, source: &impl Source) -> Result<(), Error> {
 		if self.invalid {
-			Err(Error::Invalid)
+			Err(ErrorKind::Invalid.into())
 		} else {
-			for character in source.stream() {
-				match self.verifier.update(*character) {
-					Ok(()) => {
-						self.data.push(*character);
+			let mut data = source.stream();
+
+			while let Some((character, rest)) = data.split_first() {
+				// long string bodies are copied over in bulk instead of going through the state machine byte by byte.
+				let run = if self
+					.translator
+					.as_ref()
+					.is_none_or(Translator::passes_strings)
+				{
+					self.verifier.skip_string(data)
+				} else {
+					0
+				};
+				if run > 0 {
+					self.data.extend_from_slice(&data[..run]);
+					self.consumed += run;
+					data = &data[run..];
+					continue;
+				}
+
+				data = rest;
+				match self.byte_order_mark(*character) {
+					Ok(true) => {
+						self.consumed += 1;
+						continue;
+					},
+					Ok(false) => {},
+					// the partial byte order mark is what's invalid, not `character`, which is applied as usual once
+					// the byte order mark has been dropped.
+					Err(partial) => {
+						let offset = self.consumed - partial;
+
+						match self.on_error {
+							OnError::Skip => self.skip(offset..self.consumed),
+							OnError::Stop => {
+								let error = self.error(ErrorKind::Invalid, offset, BOM[0]);
+
+								self.consumed += 1 + data.len();
+								return Err(error);
+							},
+							OnError::Poison => {
+								self.invalid = true;
+								return Err(self.error(ErrorKind::Invalid, offset, BOM[0]));
+							},
+						}
 					},
-					Err(e) => {
-						self.invalid = true;
-						return Err(e);
+				}
+
+				match self.apply(*character) {
+					Ok(()) => {},
+					Err(e) => match self.on_error {
+						OnError::Skip => self.skip(self.consumed..self.consumed + 1),
+						OnError::Stop => {
+							let error = self.error(e.kind(), self.consumed, *character);
+
+							// the rejected byte and the rest of the source are dropped, but later offsets still count them.
+							self.consumed += 1 + data.len();
+							return Err(error);
+						},
+						OnError::Poison => {
+							self.invalid = true;
+							return Err(self.error(e.kind(), self.consumed, *character));
+						},
 					},
 				}
+
+				self.consumed += 1;
 			}
 
 			Ok(())
 		}
 	}
 
+	/// Records that the bytes at `offsets` were dropped, extending the last range if it ends where they start.
+	fn skip(&mut self, offsets: Range<usize>) {
+		match self.skipped.last_mut() {
+			Some(last) if last.end == offsets.start => last.end = offsets.end,
+			_ => self.skipped.push(offsets),
+		}
+	}
+
+	/// Applies `character` to the verifier and keeps it, translating it into strict JSON first if needed.
+	fn apply(&mut self, character: u8) -> Result<(), Error> {
+		let Some(translator) = &mut self.translator else {
+			self.verifier.update(character)?;
+			self.data.push(character);
+
+			return Ok(());
+		};
+
+		let mut output = Output::new(&mut self.data, &mut self.verifier);
+
+		Ok(translator.translate(character, &mut output)?)
+	}
+
+	/// Returns whether `character` is part of a UTF-8 byte order mark at the start of the input (which may be split
+	/// across updates), or the length of the partial byte order mark before it if it cuts one short.
+	fn byte_order_mark(&mut self, character: u8) -> Result<bool, usize> {
+		if self.bom == BOM.len() || self.consumed != self.bom {
+			return Ok(false);
+		}
+
+		if character == BOM[self.bom] {
+			self.bom += 1;
+			return Ok(true);
+		}
+
+		let partial = self.bom;
+		self.bom = BOM.len();
+
+		if partial > 0 {
+			Err(partial)
+		} else {
+			Ok(false)
+		}
+	}
+
+	/// Describes an error caused by `character` at `offset`, along with the input leading up to it. Building that
+	/// context takes time proportional to the input, so it's only done for errors that are returned.
+	fn error(&self, kind: ErrorKind, offset: usize, character: u8) -> Error {
+		Error::at(kind, offset).with_context(&self.data, character)
+	}
+
 	/// Returns the underlying byte stream, or an error if the JSON object is invalid.
 	///
 	/// # Errors
@@ -150,7 +431,7 @@ impl Builder {
 	/// Returns an error if the JSON object is invalid.
 	pub fn bytes(self) -> Result<Vec<u8>, Error> {
 		if self.invalid {
-			return Err(Error::Invalid);
+			return Err(ErrorKind::Invalid.into());
 		}
 
 		Ok(self.data)
@@ -164,7 +445,7 @@ impl Builder {
 	pub fn string(self) -> Result<String, Error> {
 		let data = self.bytes()?;
 
-		String::from_utf8(data).map_err(|_| Error::Utf8)
+		String::from_utf8(data).map_err(|_| ErrorKind::Utf8.into())
 	}
 
 	/// Returns the completed JSON object as a byte stream.
@@ -174,21 +455,251 @@ impl Builder {
 	/// Returns an error if the JSON object is invalid.
 	pub fn completed_bytes(mut self) -> Result<Vec<u8>, Error> {
 		if self.invalid {
-			Err(Error::Invalid)
+			Err(ErrorKind::Invalid.into())
 		} else {
-			if self.verifier.status() == Status::Continue {
-				let (until, tokens) = self.verifier.complete();
-
-				if let Some(until) = until {
-					self.data.truncate(if until == 0 { 1 } else { until });
-				}
-				self.data.extend(tokens);
+			if let Some(completion) = self.completion() {
+				completion.apply(&mut self.data);
 			}
 
 			Ok(self.data)
 		}
 	}
 
+	/// Returns the completed JSON object as a string, with every value on its own line, indented by `indent` per level.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the JSON object is invalid or contains invalid UTF-8.
+	///
+	/// # Example
+	/// ```
+	/// # use repair_json::Builder;
+	/// let mut builder = Builder::new();
+	/// builder.update(&r#"{"name": "miguel", "tags": [], "parents": {"mother": nu"#).unwrap();
+	///
+	/// assert_eq!(builder.completed_pretty("  ").unwrap(), r#"{
+	///   "name": "miguel",
+	///   "tags": [],
+	///   "parents": {
+	///     "mother": null
+	///   }
+	/// }"#);
+	/// ```
+	pub fn completed_pretty(self, indent: &str) -> Result<String, Error> {
+		self.completed_formatted(Formatter::pretty(indent))
+	}
+
+	/// Returns the completed JSON object as a string, without any insignificant whitespace.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the JSON object is invalid or contains invalid UTF-8.
+	pub fn completed_minified(self) -> Result<String, Error> {
+		self.completed_formatted(Formatter::minified())
+	}
+
+	fn completed_formatted(self, mut formatter: Formatter) -> Result<String, Error> {
+		if self.invalid {
+			return Err(ErrorKind::Invalid.into());
+		}
+
+		let mut output = Vec::with_capacity(self.data.len());
+		match self.completion() {
+			Some(completion) => {
+				formatter.write(&self.data[..completion.end(self.data.len())], &mut output);
+				formatter.write(completion.tail.bytes(), &mut output);
+				formatter.write(&completion.closers, &mut output);
+			},
+			None => formatter.write(&self.data, &mut output),
+		}
+
+		String::from_utf8(output).map_err(|_| ErrorKind::Utf8.into())
+	}
+
+	/// Returns a report of the repairs needed to complete this JSON object, as performed by [`Builder::completed_bytes`].
+	///
+	/// # Errors
+	///
+	/// Returns an error if the JSON object is invalid.
+	///
+	/// # Example
+	/// ```
+	/// # use repair_json::{Builder, Repair};
+	/// let mut builder = Builder::new();
+	/// builder.update(&r#"{ "tags": ["a", tr"#).unwrap();
+	///
+	/// assert_eq!(builder.report().unwrap().repairs(), &[
+	///     Repair::CompletedLiteral { offset: 16, partial: "tr", literal: "true" },
+	///     Repair::Closed { offset: 20, tokens: "]}".to_string() },
+	/// ]);
+	/// ```
+	pub fn report(&self) -> Result<RepairReport, Error> {
+		if self.invalid {
+			return Err(ErrorKind::Invalid.into());
+		}
+
+		Ok(self
+			.completion()
+			.map_or_else(RepairReport::default, |completion| {
+				RepairReport::new(&completion, self.data.len())
+			}))
+	}
+
+	/// Returns the JSON Patch ([RFC 6902](https://datatracker.ietf.org/doc/html/rfc6902)) operations that turn the
+	/// completed JSON object at the time of the previous call into the current completed JSON object.
+	///
+	/// # Remarks
+	///
+	/// The first call (and the first call after a [reset](Builder::reset)) returns a single `add` operation for the whole
+	/// document. Checkpoints don't affect the last seen document, so patches remain correct after a [rollback](Builder::rollback).
+	///
+	/// Each call copies and parses the whole completed document, so it costs time proportional to the input so far
+	/// rather than to what changed since the previous call; call it once per batch of updates rather than after every
+	/// byte. If an object repeats a key, the last occurrence is the key's value (as with `JSON.parse`), and it stays
+	/// at the position of the first.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the JSON object is invalid or contains invalid UTF-8.
+	///
+	/// # Example
+	/// ```
+	/// # use repair_json::Builder;
+	/// let mut builder = Builder::new();
+	///
+	/// builder.update(&r#"{ "message": "Hel"#).unwrap();
+	/// assert_eq!(builder.patch().unwrap().to_string(), r#"[{"op":"add","path":"","value":{"message":"Hel"}}]"#);
+	///
+	/// builder.update(&r#"lo", "tags": ["#).unwrap();
+	/// assert_eq!(
+	///     builder.patch().unwrap().to_string(),
+	///     r#"[{"op":"replace","path":"/message","value":"Hello"},{"op":"add","path":"/tags","value":[]}]"#
+	/// );
+	/// ```
+	pub fn patch(&mut self) -> Result<Patch, Error> {
+		if self.invalid {
+			return Err(ErrorKind::Invalid.into());
+		}
+
+		let mut data = self.data.clone();
+		if let Some(completion) = self.completion() {
+			completion.apply(&mut data);
+		}
+
+		let Some(current) = Value::parse(&data)? else {
+			return Ok(Patch::default());
+		};
+
+		let patch = current.diff(self.patched.as_ref());
+		self.patched = Some(current);
+
+		Ok(patch)
+	}
+
+	/// Returns a borrowed view of the completed JSON object, as the kept prefix of the input and the suffix that
+	/// completes it.
+	///
+	/// # Remarks
+	///
+	/// Unlike [`Builder::completed_bytes`], this doesn't copy the input, so taking a snapshot after every chunk of a
+	/// long stream only costs as much as the suffix. Writing both slices (e.g. with [`std::io::Write::write_all`])
+	/// produces the same bytes as [`Builder::completed_bytes`].
+	///
+	/// # Errors
+	///
+	/// Returns an error if the JSON object is invalid.
+	///
+	/// # Example
+	/// ```
+	/// # use repair_json::Builder;
+	/// let mut builder = Builder::new();
+	///
+	/// builder.update(&r#"{ "tags": ["a", "b"#).unwrap();
+	/// assert_eq!(builder.snapshot().unwrap(), (&br#"{ "tags": ["a", "b"#[..], &br#""]}"#[..]));
+	///
+	/// builder.update(&r#"", "#).unwrap();
+	/// assert_eq!(builder.snapshot().unwrap(), (&br#"{ "tags": ["a", "b""#[..], &b"]}"[..]));
+	/// ```
+	pub fn snapshot(&mut self) -> Result<(&[u8], &[u8]), Error> {
+		if self.invalid {
+			return Err(ErrorKind::Invalid.into());
+		}
+
+		self.suffix.clear();
+
+		let Some(completion) = self.completion() else {
+			return Ok((&self.data, &self.suffix));
+		};
+
+		self.suffix.extend_from_slice(completion.tail.bytes());
+		self.suffix.extend_from_slice(&completion.closers);
+
+		Ok((&self.data[..completion.end(self.data.len())], &self.suffix))
+	}
+
+	/// Returns the completed JSON object as [`IoSlice`]s, which can be written with [`Write::write_vectored`] without
+	/// copying the input.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the JSON object is invalid.
+	///
+	/// # Example
+	/// ```
+	/// # use repair_json::Builder;
+	/// # use std::io::Write;
+	/// let mut builder = Builder::new();
+	/// builder.update(&r#"{ "name": "mig"#).unwrap();
+	///
+	/// let mut output = vec![];
+	/// let written = output.write_vectored(&builder.io_slices().unwrap()).unwrap();
+	/// assert_eq!(written, 16);
+	/// assert_eq!(output, br#"{ "name": "mig"}"#);
+	/// ```
+	///
+	/// [`Write::write_vectored`]: std::io::Write::write_vectored
+	#[cfg(feature = "std")]
+	pub fn io_slices(&mut self) -> Result<[IoSlice<'_>; 2], Error> {
+		let (prefix, suffix) = self.snapshot()?;
+
+		Ok([IoSlice::new(prefix), IoSlice::new(suffix)])
+	}
+
+	/// Returns the completed JSON object as a string, replacing invalid UTF-8 sequences with U+FFFD.
+	///
+	/// # Remarks
+	///
+	/// Invalid UTF-8 can only appear inside strings, and only when the `Builder` was created with
+	/// [`Options::allow_invalid_utf8`], so the result is always valid JSON.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the JSON object is invalid.
+	///
+	/// # Example
+	/// ```
+	/// # use repair_json::{Builder, Options};
+	/// let options = Options::builder().allow_invalid_utf8(true).build().unwrap();
+	/// let mut builder = Builder::with_options(&options).unwrap();
+	///
+	/// builder.update(&b"{ \"name\": \"mig\xffuel\", \"emoji\": \"\xf0\x9f".as_slice()).unwrap();
+	/// assert_eq!(builder.completed_string_lossy().unwrap(), "{ \"name\": \"mig\u{FFFD}uel\", \"emoji\": \"\"}");
+	/// ```
+	pub fn completed_string_lossy(self) -> Result<String, Error> {
+		let data = self.completed_bytes()?;
+
+		Ok(String::from_utf8(data)
+			.unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned()))
+	}
+
+	fn completion(&self) -> Option<Completion> {
+		if self.verifier.status() == Status::Continue {
+			Some(self.verifier.complete())
+		} else {
+			None
+		}
+	}
+
 	/// Returns the completed JSON object as a string.
 	///
 	/// # Errors
@@ -197,7 +708,117 @@ impl Builder {
 	pub fn completed_string(self) -> Result<String, Error> {
 		let data = self.completed_bytes()?;
 
-		String::from_utf8(data).map_err(|_| Error::Utf8)
+		String::from_utf8(data).map_err(|_| ErrorKind::Utf8.into())
+	}
+}
+
+/// An opaque marker of the state of a [`Builder`], created by [`Builder::checkpoint`].
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+	id: usize,
+	stamp: usize,
+	epoch: usize,
+	length: usize,
+	bom: usize,
+	translator: Option<Translator>,
+	invalid: bool,
+	consumed: usize,
+	skipped: usize,
+	verifier: Verifier,
+}
+
+/// The source of [`History`] identifiers and of the stamps that order checkpoints and clones, so that checkpoints
+/// taken from one builder can't be restored on another.
+#[cfg(target_has_atomic = "ptr")]
+static STAMPS: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+/// Returns a stamp that is greater than every one returned before it.
+fn stamp() -> usize {
+	#[cfg(target_has_atomic = "ptr")]
+	let stamp = STAMPS.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+	// without atomics, checkpoints can't be told apart from those of other builders.
+	#[cfg(not(target_has_atomic = "ptr"))]
+	let stamp = 0;
+
+	stamp
+}
+
+/// Tracks which checkpoints of a [`Builder`] can still be restored.
+#[derive(Debug)]
+pub struct History {
+	/// Identifies the input of a builder since it was created, cloned or reset.
+	id: usize,
+	/// The `(id, stamp)` of the builders this one was cloned from, where `stamp` was taken at the time of the clone.
+	/// Their checkpoints from before then are shared with this one.
+	ancestors: Vec<(usize, usize)>,
+	/// The number of rollbacks so far.
+	epoch: usize,
+	/// The `(epoch, length)` of the rollbacks that truncated the data further than every later one, so both increase.
+	/// A checkpoint is stale if a rollback since it was taken truncated the data below its length.
+	truncations: Vec<(usize, usize)>,
+}
+
+impl History {
+	fn new() -> Self {
+		Self {
+			id: stamp(),
+			ancestors: vec![],
+			epoch: 0,
+			truncations: vec![],
+		}
+	}
+
+	/// Returns the history of a clone, which keeps the past of this one but diverges from it from now on.
+	fn fork(&self) -> Self {
+		let mut ancestors = self.ancestors.clone();
+		ancestors.push((self.id, stamp()));
+
+		Self {
+			id: stamp(),
+			ancestors,
+			epoch: self.epoch,
+			truncations: self.truncations.clone(),
+		}
+	}
+
+	/// Checks that `checkpoint` was taken from this history (or from an ancestor before it was cloned), and that
+	/// nothing it captured has been discarded since.
+	fn check(&self, checkpoint: &Checkpoint) -> Result<(), RollbackError> {
+		let shared = checkpoint.id == self.id
+			|| self
+				.ancestors
+				.iter()
+				.any(|(id, stamp)| checkpoint.id == *id && checkpoint.stamp < *stamp);
+		if !shared {
+			return Err(RollbackError::Foreign);
+		}
+
+		let later = self
+			.truncations
+			.partition_point(|(epoch, _)| *epoch < checkpoint.epoch);
+		if self
+			.truncations
+			.get(later)
+			.is_some_and(|(_, length)| checkpoint.length > *length)
+		{
+			return Err(RollbackError::Stale);
+		}
+
+		Ok(())
+	}
+
+	/// Records a rollback that truncates the data to `length`.
+	fn truncate(&mut self, length: usize) {
+		while self
+			.truncations
+			.last()
+			.is_some_and(|(_, truncated)| *truncated >= length)
+		{
+			self.truncations.pop();
+		}
+
+		self.truncations.push((self.epoch, length));
+		self.epoch += 1;
 	}
 }
 
@@ -209,7 +830,7 @@ pub trait Source {
 impl Source for u8 {
 	fn stream(&self) -> &[u8] {
 		// safety: the memory layout of a singular `T` is always the same as an array of one `T`.
-		unsafe { std::slice::from_raw_parts(self, 1) }
+		unsafe { core::slice::from_raw_parts(self, 1) }
 	}
 }
 
@@ -0,0 +1,327 @@
+#![warn(clippy::all, clippy::pedantic, clippy::nursery)]
+
+mod watch;
+
+use clap::{Parser, ValueEnum};
+use repair_json::{Builder, ConfigError, Dialect, Error, ErrorKind, Formatter, OnError, Options};
+use std::{
+	fs::File,
+	io::{self, BufRead, BufReader, Read, Write},
+	path::PathBuf,
+	process::ExitCode,
+};
+
+/// Repair incomplete JSON so it can be parsed as it is received.
+#[derive(Debug, Parser)]
+#[command(
+	version,
+	about,
+	after_help = "Exit codes:\n  0  success\n  1  the input could not be read\n  2  invalid arguments\n  3  the input is not valid JSON\n  4  the input is not valid UTF-8\n  5  the input exceeded the maximum depth or another limit"
+)]
+struct Args {
+	/// Files to repair. Reads from stdin if none are given, or when `-` is given.
+	files: Vec<PathBuf>,
+
+	/// Drop bytes that would make the JSON invalid instead of failing.
+	#[arg(short, long)]
+	lenient: bool,
+
+	/// Maximum nesting depth of arrays and objects.
+	#[arg(short = 'd', long, value_parser = clap::value_parser!(u64).range(1..))]
+	max_depth: Option<u64>,
+
+	/// Syntax of the input, which is translated into strict JSON.
+	#[arg(long, value_enum, default_value_t = Syntax::Json)]
+	syntax: Syntax,
+
+	/// Print a repaired (and minified) snapshot after every line of input.
+	#[arg(short, long)]
+	follow: bool,
+
+	/// Tail the input, redrawing a pretty-printed snapshot after every chunk.
+	#[arg(short, long, conflicts_with = "follow")]
+	watch: bool,
+}
+
+impl Args {
+	fn builder(&self) -> Result<Builder, Failure> {
+		let options = Options::builder()
+			.on_error(if self.lenient {
+				OnError::Skip
+			} else {
+				OnError::Poison
+			})
+			.maximum_depth(self.max_depth.map_or(usize::MAX, |depth| {
+				usize::try_from(depth).unwrap_or(usize::MAX)
+			}))
+			.dialect(self.syntax.into())
+			.build()?;
+
+		Ok(Builder::with_options(&options)?)
+	}
+}
+
+/// The input dialects that can be selected from the command line.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Syntax {
+	Json,
+	Json5,
+	Jsonc,
+	Python,
+}
+
+impl From<Syntax> for Dialect {
+	fn from(syntax: Syntax) -> Self {
+		match syntax {
+			Syntax::Json => Self::Json,
+			Syntax::Json5 => Self::Json5,
+			Syntax::Jsonc => Self::Jsonc,
+			Syntax::Python => Self::Python,
+		}
+	}
+}
+
+/// Everything that can go wrong while repairing an input.
+pub enum Failure {
+	Io(io::Error),
+	Config(ConfigError),
+	Repair(Error),
+}
+
+impl Failure {
+	fn exit_code(&self) -> ExitCode {
+		ExitCode::from(match self {
+			Self::Io(_) => 1,
+			Self::Config(_) => 2,
+			Self::Repair(error) => match error.kind() {
+				ErrorKind::Invalid => 3,
+				ErrorKind::Utf8 => 4,
+				ErrorKind::Exceeded
+				| ErrorKind::TooLong
+				| ErrorKind::StringTooLong
+				| ErrorKind::TooManyKeys
+				| ErrorKind::ArrayTooLong => 5,
+			},
+		})
+	}
+}
+
+impl From<io::Error> for Failure {
+	fn from(error: io::Error) -> Self {
+		Self::Io(error)
+	}
+}
+
+impl From<ConfigError> for Failure {
+	fn from(error: ConfigError) -> Self {
+		Self::Config(error)
+	}
+}
+
+impl From<Error> for Failure {
+	fn from(error: Error) -> Self {
+		Self::Repair(error)
+	}
+}
+
+fn main() -> ExitCode {
+	run(
+		&Args::parse(),
+		&mut io::stdin().lock(),
+		&mut io::stdout().lock(),
+		&mut io::stderr().lock(),
+	)
+}
+
+/// Repairs every input in `args` (reading `-` from `stdin`) to `stdout`, reporting failures to `stderr`.
+fn run(
+	args: &Args,
+	stdin: &mut impl Read,
+	stdout: &mut impl Write,
+	stderr: &mut impl Write,
+) -> ExitCode {
+	let inputs = if args.files.is_empty() {
+		vec![PathBuf::from("-")]
+	} else {
+		args.files.clone()
+	};
+
+	for input in inputs {
+		if let Err(failure) = repair(args, &input, stdin, stdout) {
+			// there's nowhere left to report a failure to write to stderr.
+			let _ = match &failure {
+				Failure::Io(error) if error.kind() == io::ErrorKind::BrokenPipe => {
+					return ExitCode::SUCCESS
+				},
+				Failure::Io(error) => writeln!(stderr, "repair-json: {}: {error}", input.display()),
+				Failure::Config(error) => writeln!(stderr, "repair-json: {error}"),
+				Failure::Repair(error) => {
+					writeln!(stderr, "repair-json: {}: {error}", input.display())
+				},
+			};
+
+			return failure.exit_code();
+		}
+	}
+
+	ExitCode::SUCCESS
+}
+
+fn repair(
+	args: &Args,
+	input: &PathBuf,
+	stdin: &mut impl Read,
+	stdout: &mut impl Write,
+) -> Result<(), Failure> {
+	let is_stdin = input.as_os_str() == "-";
+	let reader: Box<dyn Read + '_> = if is_stdin {
+		Box::new(stdin)
+	} else {
+		Box::new(File::open(input)?)
+	};
+
+	let mut builder = args.builder()?;
+
+	if args.watch {
+		watch::run(&mut builder, reader, !is_stdin, stdout)?;
+	} else if args.follow {
+		let mut reader = BufReader::new(reader);
+		let (mut line, mut snapshot) = (Vec::new(), Vec::new());
+
+		while reader.read_until(b'\n', &mut line)? > 0 {
+			builder.update(&line)?;
+			line.clear();
+
+			// the snapshot is formatted from the builder as is, instead of completing a copy of it.
+			let (prefix, suffix) = builder.snapshot()?;
+			let mut formatter = Formatter::minified();
+			snapshot.clear();
+			formatter.write(prefix, &mut snapshot);
+			formatter.write(suffix, &mut snapshot);
+			snapshot.push(b'\n');
+
+			stdout.write_all(&snapshot)?;
+			stdout.flush()?;
+		}
+	} else {
+		let mut data = Vec::new();
+		BufReader::new(reader).read_to_end(&mut data)?;
+		builder.update(&data)?;
+
+		stdout.write_all(&builder.completed_bytes()?)?;
+		stdout.write_all(b"\n")?;
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{run, Args, Failure};
+	use clap::Parser;
+	use repair_json::ConfigError;
+	use std::process::ExitCode;
+
+	/// Runs the binary with `args` on `stdin`, returning its exit code, stdout and stderr.
+	fn repair_json(args: &[&str], stdin: &[u8]) -> (ExitCode, String, String) {
+		let args = Args::try_parse_from([&["repair-json"], args].concat()).unwrap();
+		let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+
+		let code = run(&args, &mut &*stdin, &mut stdout, &mut stderr);
+		(
+			code,
+			String::from_utf8(stdout).unwrap(),
+			String::from_utf8(stderr).unwrap(),
+		)
+	}
+
+	#[test]
+	fn repairs_stdin() {
+		assert_eq!(
+			repair_json(&[], br#"{ "a": [1, "b"#),
+			(
+				ExitCode::SUCCESS,
+				"{ \"a\": [1, \"b\"]}\n".to_string(),
+				String::new()
+			)
+		);
+		assert_eq!(repair_json(&["-"], b"[tr").1, "[true]\n");
+	}
+
+	#[test]
+	fn exits_with_a_code_for_each_failure() {
+		let (code, stdout, stderr) = repair_json(&["does/not/exist.json"], b"");
+		assert_eq!((code, stdout.as_str()), (ExitCode::from(1), ""));
+		assert!(
+			stderr.starts_with("repair-json: does/not/exist.json: "),
+			"{stderr}"
+		);
+
+		let error = Args::try_parse_from(["repair-json", "--max-depth", "0"]).unwrap_err();
+		assert_eq!(error.exit_code(), 2);
+		let error = Args::try_parse_from(["repair-json", "--follow", "--watch"]).unwrap_err();
+		assert_eq!(error.exit_code(), 2);
+		assert_eq!(
+			Failure::from(ConfigError::ZeroMaximumLength).exit_code(),
+			ExitCode::from(2)
+		);
+
+		let (code, stdout, stderr) = repair_json(&[], br#"{ "a" 1 }"#);
+		assert_eq!((code, stdout.as_str()), (ExitCode::from(3), ""));
+		assert!(
+			stderr.starts_with("repair-json: -: The input stream is not valid JSON. (at byte 6"),
+			"{stderr}"
+		);
+
+		assert_eq!(repair_json(&[], b"[\"\xFF\"]").0, ExitCode::from(4));
+		assert_eq!(
+			repair_json(&["--max-depth", "1"], b"[[1]]").0,
+			ExitCode::from(5)
+		);
+	}
+
+	#[test]
+	fn drops_invalid_bytes_when_lenient() {
+		let input = br#"{ "a": [1, 2}, 3] }"#;
+		assert_eq!(repair_json(&[], input).0, ExitCode::from(3));
+
+		for flag in ["--lenient", "-l"] {
+			assert_eq!(
+				repair_json(&[flag], input),
+				(
+					ExitCode::SUCCESS,
+					"{ \"a\": [1, 2, 3] }\n".to_string(),
+					String::new()
+				)
+			);
+		}
+	}
+
+	#[test]
+	fn limits_the_depth() {
+		assert_eq!(repair_json(&["--max-depth", "2"], b"[[1]").1, "[[1]]\n");
+		assert_eq!(repair_json(&["-d", "2"], b"[[[1]").0, ExitCode::from(5));
+
+		// a lenient repair drops the bracket that would be too deep instead of failing, so its closer ends the outer array.
+		assert_eq!(
+			repair_json(&["--lenient", "--max-depth", "1"], b"[[1], 2"),
+			(ExitCode::SUCCESS, "[1] \n".to_string(), String::new())
+		);
+	}
+
+	#[test]
+	fn prints_a_snapshot_per_line_when_following() {
+		assert_eq!(
+			repair_json(&["--follow"], b"{ \"a\": \"b\",\n\"c\": [\"d\",\n\"e\"]}\n"),
+			(
+				ExitCode::SUCCESS,
+				"{\"a\":\"b\"}\n{\"a\":\"b\",\"c\":[\"d\"]}\n{\"a\":\"b\",\"c\":[\"d\",\"e\"]}\n"
+					.to_string(),
+				String::new()
+			)
+		);
+
+		let (code, stdout, _) = repair_json(&["-f"], b"[\"a\",\n\"b\" \"c\"]\n");
+		assert_eq!((code, stdout.as_str()), (ExitCode::from(3), "[\"a\"]\n"));
+	}
+}
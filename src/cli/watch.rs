@@ -0,0 +1,341 @@
+use crate::Failure;
+use repair_json::{Builder, Formatter, Repair, RepairReport, Status};
+use std::{
+	io::{self, Read, Write},
+	ops::Range,
+	thread,
+	time::Duration,
+};
+
+const INDENT: &str = "  ";
+const CLEAR: &[u8] = b"\x1b[H\x1b[2J";
+const CLEAR_BELOW: &[u8] = b"\x1b[J";
+const HIGHLIGHT: &[u8] = b"\x1b[1;33m";
+const RESET: &[u8] = b"\x1b[0m";
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Feeds `reader` into `builder` chunk by chunk, redrawing the repaired document after each one.
+///
+/// When `tail` is set, reaching the end of the input waits for more data (like `tail -f`) until the document is complete.
+pub fn run(
+	builder: &mut Builder,
+	mut reader: impl Read,
+	tail: bool,
+	out: &mut impl Write,
+) -> Result<(), Failure> {
+	let mut screen = Screen::new();
+	let mut chunk = [0; 4096];
+
+	loop {
+		let read = match reader.read(&mut chunk) {
+			Ok(read) => read,
+			Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+			Err(e) => return Err(e.into()),
+		};
+
+		if read == 0 {
+			if !tail || builder.status() == Status::Valid {
+				return Ok(());
+			}
+
+			thread::sleep(POLL_INTERVAL);
+			continue;
+		}
+
+		builder.update(&&chunk[..read])?;
+		screen.draw(builder, out)?;
+	}
+}
+
+/// The pretty-printed document on screen, which is formatted and redrawn as the input grows instead of from scratch.
+struct Screen {
+	/// The kept input formatted up to the last checkpoint. It only ever grows, since the input does too.
+	formatted: Vec<u8>,
+	/// The offsets of the line breaks in `formatted`.
+	breaks: Vec<usize>,
+	/// Where formatting can resume: how much of the input was formatted, how long its output is, and the formatter
+	/// after it. There is one for every draw, so resuming usually formats about a chunk at most.
+	checkpoints: Vec<(usize, usize, Formatter<'static>)>,
+	/// The document on screen, and how much of it is shared with `formatted`.
+	document: Vec<u8>,
+	shared: usize,
+	highlight: Option<Range<usize>>,
+	/// The number of lines on screen, or `None` before the first draw.
+	lines: Option<usize>,
+}
+
+impl Screen {
+	fn new() -> Self {
+		Self {
+			formatted: Vec::new(),
+			breaks: Vec::new(),
+			checkpoints: vec![(0, 0, Formatter::pretty(INDENT))],
+			document: Vec::new(),
+			shared: 0,
+			highlight: None,
+			lines: None,
+		}
+	}
+
+	/// Formats the completed document (the kept `prefix` of the input followed by the `suffix` that completes it)
+	/// into `self.document`, returning how many of its bytes are unchanged since the last call.
+	fn format(&mut self, prefix: &[u8], suffix: &[u8]) -> usize {
+		let (committed, _, mut formatter) = self.checkpoints[self.checkpoints.len() - 1];
+		if prefix.len() > committed {
+			let start = self.formatted.len();
+			formatter.write(&prefix[committed..], &mut self.formatted);
+
+			self.breaks.extend(
+				(start..self.formatted.len()).filter(|offset| self.formatted[*offset] == b'\n'),
+			);
+			self.checkpoints
+				.push((prefix.len(), self.formatted.len(), formatter));
+		}
+
+		// the kept input can shrink when an incomplete value is dropped, so resume from the last checkpoint it reaches.
+		let index = self
+			.checkpoints
+			.partition_point(|(position, ..)| *position <= prefix.len())
+			- 1;
+		let (position, length, mut formatter) = self.checkpoints[index];
+
+		let unchanged = self.shared.min(length);
+		self.document.truncate(unchanged);
+		self.document
+			.extend_from_slice(&self.formatted[unchanged..length]);
+		formatter.write(&prefix[position..], &mut self.document);
+		formatter.write(suffix, &mut self.document);
+
+		self.shared = length;
+		unchanged
+	}
+
+	/// Draws the repaired document, only redrawing the lines that changed since the last draw.
+	fn draw(&mut self, builder: &mut Builder, out: &mut impl Write) -> Result<(), Failure> {
+		let report = builder.report()?;
+		let status = format!("\n\n{} bytes, {:?}\n", builder.len(), builder.status());
+
+		let (prefix, suffix) = builder.snapshot()?;
+		let mut changed = self.format(prefix, suffix);
+
+		// a highlight that moved has to be drawn (or removed) again too.
+		let highlight = in_progress(&self.document, &report);
+		for range in [&self.highlight, &highlight].into_iter().flatten() {
+			changed = changed.min(range.start);
+		}
+
+		// everything before `changed` is in `formatted`, so its line breaks are already known.
+		let line = self.breaks.partition_point(|offset| *offset < changed);
+		let mut start = line.checked_sub(1).map_or(0, |line| self.breaks[line] + 1);
+		let lines = line + newlines(&self.document[start..]) + newlines(status.as_bytes());
+
+		match self.lines {
+			// moving up is limited to what's on screen, so only redraw in place if there are at least as many lines
+			// below the cursor afterwards as there were before.
+			Some(previous) if lines >= previous => {
+				if previous > line {
+					write!(out, "\x1b[{}F", previous - line)?;
+				}
+				out.write_all(CLEAR_BELOW)?;
+			},
+			_ => {
+				start = 0;
+				out.write_all(CLEAR)?;
+			},
+		}
+
+		let document = &self.document;
+		match &highlight {
+			Some(range) => {
+				out.write_all(&document[start..range.start])?;
+				out.write_all(HIGHLIGHT)?;
+				out.write_all(&document[range.clone()])?;
+				out.write_all(RESET)?;
+				out.write_all(&document[range.end..])?;
+			},
+			None => out.write_all(&document[start..])?,
+		}
+
+		out.write_all(status.as_bytes())?;
+		out.flush()?;
+
+		self.highlight = highlight;
+		self.lines = Some(lines);
+
+		Ok(())
+	}
+}
+
+fn newlines(data: &[u8]) -> usize {
+	data.split(|c| *c == b'\n').count() - 1
+}
+
+const fn is_whitespace(character: u8) -> bool {
+	matches!(character, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+/// Finds the value that is still being streamed in, which is always the last one before the synthesized closers.
+fn in_progress(document: &[u8], report: &RepairReport) -> Option<Range<usize>> {
+	let mut closers = 0;
+	let mut string = false;
+	let mut literal = false;
+
+	for repair in report.repairs() {
+		match repair {
+			Repair::Truncated { .. } => return None,
+			Repair::TrimmedCharacter { .. } => {},
+			Repair::ClosedString { .. } => string = true,
+			Repair::CompletedLiteral { .. } => literal = true,
+			Repair::Closed { tokens, .. } => closers = tokens.len(),
+		}
+	}
+
+	let skip_whitespace = |end: usize| {
+		document[..end]
+			.iter()
+			.rposition(|c| !is_whitespace(*c))
+			.map_or(0, |n| n + 1)
+	};
+
+	let mut end = document.len();
+	for _ in 0..closers {
+		end = skip_whitespace(end).checked_sub(1)?;
+	}
+	end = skip_whitespace(end);
+
+	let start = if string {
+		let mut start = end.checked_sub(1)?;
+
+		loop {
+			start = document[..start].iter().rposition(|c| *c == b'"')?;
+
+			let backslashes = document[..start]
+				.iter()
+				.rev()
+				.take_while(|c| **c == b'\\')
+				.count();
+			if backslashes % 2 == 0 {
+				break start;
+			}
+		}
+	} else if literal || document[..end].last().is_some_and(u8::is_ascii_digit) {
+		document[..end]
+			.iter()
+			.rposition(|c| !(c.is_ascii_alphanumeric() || matches!(c, b'-' | b'+' | b'.')))
+			.map_or(0, |n| n + 1)
+	} else {
+		return None;
+	};
+
+	Some(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{in_progress, run, Screen, CLEAR, CLEAR_BELOW, HIGHLIGHT, INDENT, RESET};
+	use repair_json::Builder;
+
+	#[test]
+	fn formats_the_document_as_it_grows() {
+		// numbers before a comma and keys without a value are dropped again, which shrinks the kept input. Model
+		// output often starts with a line break.
+		let inputs: [&[u8]; 2] = [
+			br#"{"name": "mig\"uel", "values": [1, 2.5, -3e2, true, null], "nested": {"empty": [], "deep": [[{"a": "b"}]]}, "last": fals"#,
+			b"\n{\n  \"a\": [\n    {}",
+		];
+
+		for input in inputs {
+			for chunk_size in [1, 3, 7, input.len()] {
+				let mut builder = Builder::new();
+				let mut screen = Screen::new();
+
+				for chunk in input.chunks(chunk_size) {
+					builder.update(&chunk).unwrap();
+
+					let (prefix, suffix) = builder.snapshot().unwrap();
+					screen.format(prefix, suffix);
+					assert_eq!(
+						String::from_utf8(screen.document.clone()).unwrap(),
+						builder.clone().completed_pretty(INDENT).unwrap()
+					);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn finds_the_value_in_progress() {
+		for (input, expected) in [
+			(r#"{"a": "b\"c"#, Some(r#""b\"c""#)),
+			(r#"{"a": "b\\"#, Some(r#""b\\""#)),
+			(r#"{"a": tr"#, Some("true")),
+			(r#"{"a": 12"#, Some("12")),
+			("[[1], [fals", Some("false")),
+			(r#"{"a": [1, 2]"#, None),
+			(r#"{"a": "b", "c"#, None),
+			("", None),
+		] {
+			let mut builder = Builder::new();
+			builder.update(&input).unwrap();
+
+			let report = builder.report().unwrap();
+			let document = builder.completed_pretty(INDENT).unwrap();
+			assert_eq!(
+				in_progress(document.as_bytes(), &report).map(|range| &document[range]),
+				expected,
+				"{input}"
+			);
+		}
+	}
+
+	#[test]
+	fn only_redraws_the_lines_that_changed() {
+		let mut builder = Builder::new();
+		let mut screen = Screen::new();
+		let mut draw = |input: &str| {
+			let mut out = Vec::new();
+			builder.update(&input).unwrap();
+			assert!(screen.draw(&mut builder, &mut out).is_ok());
+
+			String::from_utf8(out).unwrap()
+		};
+
+		let clear = std::str::from_utf8(CLEAR).unwrap();
+		let clear_below = std::str::from_utf8(CLEAR_BELOW).unwrap();
+		let (highlight, reset) = (
+			std::str::from_utf8(HIGHLIGHT).unwrap(),
+			std::str::from_utf8(RESET).unwrap(),
+		);
+
+		assert_eq!(
+			draw(r#"{"a": "x"#),
+			format!("{clear}{{\n  \"a\": {highlight}\"x\"{reset}\n}}\n\n8 bytes, Continue\n")
+		);
+
+		// the highlighted line and everything below it is redrawn, since the cursor is after the status line.
+		assert_eq!(
+			draw(r#"y", "b": [1"#),
+			format!(
+				"\x1b[4F{clear_below}  \"a\": \"xy\",\n  \"b\": [\n    {highlight}1{reset}\n  ]\n}}\n\n19 bytes, Continue\n"
+			)
+		);
+		assert_eq!(
+			draw("2"),
+			format!(
+				"\x1b[5F{clear_below}    {highlight}12{reset}\n  ]\n}}\n\n20 bytes, Continue\n"
+			)
+		);
+
+		// with fewer lines than before, lines scrolled out of view might have to be redrawn too.
+		assert!(draw(", 3, ").starts_with(clear));
+	}
+
+	#[test]
+	fn watches_input_that_starts_with_whitespace() {
+		let mut builder = Builder::new();
+		let mut out = Vec::new();
+		assert!(run(&mut builder, &b"\n{"[..], false, &mut out).is_ok());
+
+		assert_eq!(out, b"\x1b[H\x1b[2J{}\n\n2 bytes, Continue\n");
+	}
+}
@@ -0,0 +1,262 @@
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, string::String};
+use core::fmt;
+
+/// The number of bytes of input before the error that are shown in its snippet.
+#[cfg(feature = "alloc")]
+const SNIPPET_LENGTH: usize = 40;
+
+/// An error that occurred while parsing JSON, along with where it happened.
+///
+/// # Remarks
+///
+/// Errors returned while applying input carry the offset of the offending byte. Errors from a [`Builder`](crate::Builder)
+/// also include a snippet of the input leading up to it and the JSON Pointer ([RFC 6901](https://datatracker.ietf.org/doc/html/rfc6901))
+/// of the value being written, which `Display` renders as a diagnostic with a caret under the offending byte.
+///
+/// See [`Builder::update`](crate::Builder::update) for an example.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+	kind: ErrorKind,
+	offset: Option<usize>,
+	#[cfg(feature = "alloc")]
+	context: Option<Box<Context>>,
+}
+
+/// The input surrounding an error.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Context {
+	/// The end of the input up to (and including) the offending byte.
+	snippet: String,
+	/// The number of characters in `snippet` before the offending byte.
+	column: usize,
+	path: String,
+}
+
+impl Error {
+	/// Creates an error of the given kind for the byte at `offset`.
+	pub(crate) const fn at(kind: ErrorKind, offset: usize) -> Self {
+		Self {
+			kind,
+			offset: Some(offset),
+			#[cfg(feature = "alloc")]
+			context: None,
+		}
+	}
+
+	/// Attaches the input leading up to the error, where `data` is everything accepted so far and `character` is the
+	/// offending byte.
+	#[cfg(feature = "alloc")]
+	pub(crate) fn with_context(mut self, data: &[u8], character: u8) -> Self {
+		let mut start = data.len().saturating_sub(SNIPPET_LENGTH);
+		if let Some(newline) = data[start..].iter().rposition(|c| *c == b'\n') {
+			start += newline + 1;
+		}
+		// don't start in the middle of a multi-byte character.
+		while data
+			.get(start)
+			.is_some_and(|c| c & 0b1100_0000 == 0b1000_0000)
+		{
+			start += 1;
+		}
+
+		let mut snippet = String::from_utf8_lossy(&data[start..]).into_owned();
+		let column = snippet.chars().count();
+		snippet.push(if character.is_ascii_graphic() || character == b' ' {
+			char::from(character)
+		} else {
+			char::REPLACEMENT_CHARACTER
+		});
+
+		self.context = Some(Box::new(Context {
+			snippet,
+			column,
+			path: crate::patch::pointer(data),
+		}));
+		self
+	}
+
+	/// Returns the kind of this error.
+	#[must_use]
+	pub const fn kind(&self) -> ErrorKind {
+		self.kind
+	}
+
+	/// Returns the offset of the offending byte, relative to all input provided since the last reset.
+	///
+	/// For a [`Builder`](crate::Builder), that includes bytes it dropped because of [`OnError`](crate::OnError). A
+	/// verifier treats rejected bytes as if they had never been applied, so its offsets only count accepted bytes.
+	#[must_use]
+	pub const fn offset(&self) -> Option<usize> {
+		self.offset
+	}
+
+	/// Returns the end of the input up to (and including) the offending byte.
+	#[cfg(feature = "alloc")]
+	#[must_use]
+	pub fn snippet(&self) -> Option<&str> {
+		self.context
+			.as_ref()
+			.map(|context| context.snippet.as_str())
+	}
+
+	/// Returns the JSON Pointer of the value that was being written when the error happened.
+	#[cfg(feature = "alloc")]
+	#[must_use]
+	pub fn path(&self) -> Option<&str> {
+		self.context.as_ref().map(|context| context.path.as_str())
+	}
+}
+
+impl From<ErrorKind> for Error {
+	fn from(kind: ErrorKind) -> Self {
+		Self {
+			kind,
+			offset: None,
+			#[cfg(feature = "alloc")]
+			context: None,
+		}
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.kind)?;
+
+		let Some(offset) = self.offset else {
+			return Ok(());
+		};
+
+		write!(f, " (at byte {offset}")?;
+
+		#[cfg(feature = "alloc")]
+		if let Some(context) = &self.context {
+			if !context.path.is_empty() {
+				write!(f, ", path {}", context.path)?;
+			}
+
+			return write!(
+				f,
+				")\n  {}\n  {:>width$}",
+				context.snippet,
+				"^",
+				width = context.column + 1
+			);
+		}
+
+		f.write_str(")")
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// The kinds of errors that can occur while parsing JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum ErrorKind {
+	/// The input stream is not valid JSON.
+	#[cfg_attr(feature = "std", error("The input stream is not valid JSON."))]
+	Invalid,
+
+	/// The input stream is not a valid UTF-8 sequence.
+	#[cfg_attr(
+		feature = "std",
+		error("The input stream is not a valid utf8 sequence.")
+	)]
+	Utf8,
+
+	/// The input stream contained an object exceeding the maximum specified depth.
+	#[cfg_attr(
+		feature = "std",
+		error("The input stream contained an object exceeding the maximum specified depth.")
+	)]
+	Exceeded,
+
+	/// The input stream exceeded the maximum specified length.
+	#[cfg_attr(
+		feature = "std",
+		error("The input stream exceeded the maximum specified length.")
+	)]
+	TooLong,
+
+	/// The input stream contained a string exceeding the maximum specified length.
+	#[cfg_attr(
+		feature = "std",
+		error("The input stream contained a string exceeding the maximum specified length.")
+	)]
+	StringTooLong,
+
+	/// The input stream contained an object exceeding the maximum specified number of keys.
+	#[cfg_attr(
+		feature = "std",
+		error(
+			"The input stream contained an object exceeding the maximum specified number of keys."
+		)
+	)]
+	TooManyKeys,
+
+	/// The input stream contained an array exceeding the maximum specified length.
+	#[cfg_attr(
+		feature = "std",
+		error("The input stream contained an array exceeding the maximum specified length.")
+	)]
+	ArrayTooLong,
+}
+
+// `thiserror` needs `std`, so without it we only provide `Display`.
+#[cfg(not(feature = "std"))]
+impl fmt::Display for ErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Invalid => "The input stream is not valid JSON.",
+			Self::Utf8 => "The input stream is not a valid utf8 sequence.",
+			Self::Exceeded => {
+				"The input stream contained an object exceeding the maximum specified depth."
+			},
+			Self::TooLong => "The input stream exceeded the maximum specified length.",
+			Self::StringTooLong => {
+				"The input stream contained a string exceeding the maximum specified length."
+			},
+			Self::TooManyKeys => {
+				"The input stream contained an object exceeding the maximum specified number of keys."
+			},
+			Self::ArrayTooLong => {
+				"The input stream contained an array exceeding the maximum specified length."
+			},
+		})
+	}
+}
+
+/// The reasons a [`Builder`](crate::Builder) can't be rolled back to a [`Checkpoint`](crate::Checkpoint).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum RollbackError {
+	/// The checkpoint was taken from another builder, or before this one was reset.
+	#[cfg_attr(
+		feature = "std",
+		error("The checkpoint was taken from another builder, or before it was reset.")
+	)]
+	Foreign,
+
+	/// Part of what the checkpoint captured was discarded by an earlier rollback.
+	#[cfg_attr(
+		feature = "std",
+		error("The checkpoint was discarded by an earlier rollback.")
+	)]
+	Stale,
+}
+
+// `thiserror` needs `std`, so without it we only provide `Display`.
+#[cfg(not(feature = "std"))]
+impl fmt::Display for RollbackError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Foreign => {
+				"The checkpoint was taken from another builder, or before it was reset."
+			},
+			Self::Stale => "The checkpoint was discarded by an earlier rollback.",
+		})
+	}
+}
@@ -0,0 +1,136 @@
+use alloc::vec::Vec;
+
+/// A streaming re-formatter for valid JSON, which drops insignificant whitespace and optionally re-indents the output.
+///
+/// # Remarks
+///
+/// Bytes can be written in any number of pieces, and the output of every piece only depends on the bytes before it.
+/// A copy of a `Formatter` taken between pieces can therefore resume formatting from that point, which lets a growing
+/// document (e.g. the prefix of a [`Builder::snapshot`](crate::Builder::snapshot)) be formatted once instead of from
+/// scratch every time.
+///
+/// # Example
+/// ```
+/// # use repair_json::Formatter;
+/// let mut formatter = Formatter::pretty("  ");
+/// let mut output = Vec::new();
+/// formatter.write(br#"{"tags": ["a""#, &mut output);
+///
+/// // finish the same prefix in two different ways, without formatting it again.
+/// let (mut copy, length) = (formatter, output.len());
+/// copy.write(br#", "b"]}"#, &mut output);
+/// assert_eq!(output, b"{\n  \"tags\": [\n    \"a\",\n    \"b\"\n  ]\n}");
+///
+/// output.truncate(length);
+/// formatter.write(b"]}", &mut output);
+/// assert_eq!(output, b"{\n  \"tags\": [\n    \"a\"\n  ]\n}");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Formatter<'a> {
+	indent: Option<&'a [u8]>,
+	depth: usize,
+	in_string: bool,
+	escaped: bool,
+	/// Whether an array or object was just opened, in which case the line break is deferred until we know it isn't empty.
+	opened: bool,
+}
+
+impl<'a> Formatter<'a> {
+	/// Creates a formatter that removes all insignificant whitespace.
+	#[must_use]
+	pub const fn minified() -> Self {
+		Self::new(None)
+	}
+
+	/// Creates a formatter that puts every value on its own line, indented by `indent` per level.
+	#[must_use]
+	pub const fn pretty(indent: &'a str) -> Self {
+		Self::new(Some(indent.as_bytes()))
+	}
+
+	const fn new(indent: Option<&'a [u8]>) -> Self {
+		Self {
+			indent,
+			depth: 0,
+			opened: false,
+			escaped: false,
+			in_string: false,
+		}
+	}
+
+	/// Formats `data`, appending the result to `output`.
+	pub fn write(&mut self, data: &[u8], output: &mut Vec<u8>) {
+		for &character in data {
+			if self.in_string {
+				output.push(character);
+
+				if self.escaped {
+					self.escaped = false;
+				} else if character == b'\\' {
+					self.escaped = true;
+				} else if character == b'"' {
+					self.in_string = false;
+				}
+
+				continue;
+			}
+
+			if matches!(character, b' ' | b'\t' | b'\n' | b'\r') {
+				continue;
+			}
+
+			if self.opened {
+				self.opened = false;
+
+				// keep empty arrays and objects on a single line.
+				if matches!(character, b'}' | b']') {
+					self.depth = self.depth.saturating_sub(1);
+					output.push(character);
+					continue;
+				}
+
+				self.newline(output);
+			}
+
+			match character {
+				b'"' => {
+					self.in_string = true;
+					output.push(character);
+				},
+				b'{' | b'[' => {
+					self.depth += 1;
+					self.opened = true;
+					output.push(character);
+				},
+				b'}' | b']' => {
+					// input with more closers than openers is written as is, instead of underflowing.
+					self.depth = self.depth.saturating_sub(1);
+					self.newline(output);
+					output.push(character);
+				},
+				b',' => {
+					output.push(character);
+					self.newline(output);
+				},
+				b':' => {
+					output.push(character);
+					if self.indent.is_some() {
+						output.push(b' ');
+					}
+				},
+				_ => output.push(character),
+			}
+		}
+	}
+
+	fn newline(&self, output: &mut Vec<u8>) {
+		let Some(indent) = self.indent else {
+			return;
+		};
+
+		output.push(b'\n');
+		for _ in 0..self.depth {
+			output.extend_from_slice(indent);
+		}
+	}
+}
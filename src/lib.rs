@@ -1,12 +1,48 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![warn(clippy::all, clippy::pedantic, clippy::nursery)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
 mod builder;
+mod error;
+#[cfg(feature = "alloc")]
+mod format;
+mod options;
+#[cfg(feature = "alloc")]
+mod patch;
+#[cfg(feature = "alloc")]
+mod report;
+#[cfg(feature = "alloc")]
+mod scan;
+mod stack;
 mod table;
+#[cfg(feature = "alloc")]
+mod translate;
+#[cfg(feature = "alloc")]
+mod utf16;
 mod verifier;
 
-pub use builder::Builder;
-pub use verifier::Verifier;
+#[cfg(feature = "alloc")]
+pub use builder::{Builder, Checkpoint};
+pub use error::{Error, ErrorKind, RollbackError};
+#[cfg(feature = "alloc")]
+pub use format::Formatter;
+pub use options::{ConfigError, Dialect, OnError, Options, OptionsBuilder};
+#[cfg(feature = "alloc")]
+pub use patch::{Operation, Patch};
+#[cfg(feature = "alloc")]
+pub use report::{Repair, RepairReport};
+#[cfg(feature = "alloc")]
+pub use utf16::{Endianness, Utf16};
+pub use verifier::StackVerifier;
+#[cfg(feature = "alloc")]
+pub use verifier::{Fork, Verifier};
 
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
 use builder::Source;
 
 /// The status of the JSON object.
@@ -19,24 +55,7 @@ pub enum Status {
 	Valid,
 }
 
-/// Errors that can occur while parsing JSON.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
-pub enum Error {
-	/// The input stream is not valid JSON.
-	#[error("The input stream is not valid JSON.")]
-	Invalid,
-
-	/// Conversion to a string failed because the input stream is not valid a UTF-8 sequence.
-	#[error(
-		"Conversion to a string failed because the input stream is not valid a utf8 sequence."
-	)]
-	Utf8,
-
-	/// The input stream contained an object exceeding the maximum specified depth.
-	#[error("The input stream contained an object exceeding the maximum specified depth.")]
-	Exceeded,
-}
-
+#[cfg(feature = "alloc")]
 #[allow(clippy::needless_pass_by_value)]
 /// Repairs the provided JSON object.
 ///
@@ -50,9 +69,12 @@ pub fn repair<I: Source>(input: I) -> Result<String, Error> {
 	builder.completed_string()
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
-	use crate::repair;
+	use crate::{
+		repair, Builder, ConfigError, Dialect, Endianness, ErrorKind, Formatter, OnError,
+		Operation, Options, Repair, RollbackError, StackVerifier, Status, Utf16, Verifier,
+	};
 
 	#[test]
 	fn can_complete_empty_object() {
@@ -218,4 +240,963 @@ mod tests {
 			r#"{ "users": [{ "id": 1, "name": "Miguel", "verified_at": null }, {}]}"#
 		);
 	}
+
+	#[test]
+	fn accepts_does_not_mutate_state() {
+		let mut verifier = Verifier::new();
+		for character in br#"{ "toys": ["# {
+			verifier.update(*character).unwrap();
+		}
+
+		assert!(verifier.accepts(br#""ball""#));
+		assert!(verifier.accepts(b"]}"));
+		assert!(!verifier.accepts(b"}"));
+		assert!(!verifier.accepts(br#""ball"}"#));
+		assert_eq!(verifier.len(), 11);
+
+		let mut fork = verifier.clone();
+		fork.update(b']').unwrap();
+		assert!(fork.accepts(b"}"));
+		assert!(!verifier.accepts(b"}"));
+
+		// candidates that open, close and modify arrays and objects leave every frame exactly as it was.
+		let before = format!("{verifier:?}");
+		for candidate in [
+			&b"]}"[..],
+			b"]}]",
+			br#"[{"a": [1, {}]}, "b"], "c": {"d""#,
+			br#"{"long": "a candidate that is too long to be saved inline"}]"#,
+		] {
+			assert_eq!(
+				verifier.accepts(candidate),
+				verifier.clone().accepts(candidate)
+			);
+			assert_eq!(format!("{verifier:?}"), before);
+		}
+
+		let mut fork = verifier.fork();
+		for character in br#"], "pets": {"names": ["# {
+			fork.update(*character).unwrap();
+		}
+		assert!(fork.accepts(br#""rex"]}}"#));
+		assert!(!fork.accepts(b"}"));
+		assert!(fork.update(b'}').is_err());
+
+		fork.restore();
+		assert!(fork.accepts(b"]}"));
+		fork.update(b']').unwrap();
+		drop(fork);
+		assert_eq!(format!("{verifier:?}"), before);
+	}
+
+	#[test]
+	fn rollback_recovers_from_invalid_chunk() {
+		let mut builder = Builder::new();
+		builder.update(&r#"{ "toys": ["ball""#).unwrap();
+
+		let checkpoint = builder.checkpoint();
+		assert_eq!(
+			builder.update(&r#", "car"}"#).map_err(|e| e.kind()),
+			Err(ErrorKind::Invalid)
+		);
+		assert_eq!(
+			builder.update(&"]").map_err(|e| e.kind()),
+			Err(ErrorKind::Invalid)
+		);
+
+		builder.rollback(checkpoint.clone()).unwrap();
+		builder.update(&r#", "car""#).unwrap();
+		assert_eq!(builder.len(), 24);
+
+		builder.rollback(checkpoint).unwrap();
+		assert_eq!(
+			builder.completed_string().unwrap(),
+			r#"{ "toys": ["ball"]}"#
+		);
+	}
+
+	#[test]
+	fn rollback_rejects_stale_checkpoints() {
+		let mut builder = Builder::new();
+		builder.update(&"[1, ").unwrap();
+		let outer = builder.checkpoint();
+		builder.update(&"2, ").unwrap();
+		let inner = builder.checkpoint();
+
+		// rolling back to `outer` discards what `inner` captured, even once the builder grows past it again.
+		assert_eq!(builder.rollback(outer.clone()), Ok(()));
+		builder.update(&"3, 4, ").unwrap();
+		assert_eq!(builder.rollback(inner), Err(RollbackError::Stale));
+		assert_eq!(builder.rollback(outer.clone()), Ok(()));
+		assert_eq!(builder.rollback(outer.clone()), Ok(()));
+
+		assert_eq!(
+			Builder::new().rollback(outer.clone()),
+			Err(RollbackError::Foreign)
+		);
+
+		builder.reset();
+		assert_eq!(builder.rollback(outer), Err(RollbackError::Foreign));
+	}
+
+	#[test]
+	fn rollback_tells_clones_apart() {
+		let mut builder = Builder::new();
+		builder.update(&"[1, ").unwrap();
+		let shared = builder.checkpoint();
+
+		let mut clone = builder.clone();
+		builder.update(&r#""a", "#).unwrap();
+		let original = builder.checkpoint();
+		clone.update(&"2, 3").unwrap();
+		let cloned = clone.checkpoint();
+
+		// checkpoints from before the clone are shared, but the two diverge after it.
+		assert_eq!(clone.rollback(original), Err(RollbackError::Foreign));
+		assert_eq!(
+			builder.rollback(cloned.clone()),
+			Err(RollbackError::Foreign)
+		);
+		assert_eq!(clone.rollback(shared.clone()), Ok(()));
+		assert_eq!(builder.rollback(shared.clone()), Ok(()));
+		assert_eq!(clone.rollback(cloned), Err(RollbackError::Stale));
+
+		// so are those of the builder a clone was cloned from.
+		let mut grandchild = clone.clone();
+		assert_eq!(grandchild.rollback(shared), Ok(()));
+		assert_eq!(grandchild.len(), 4);
+	}
+
+	#[test]
+	fn can_skip_or_stop_on_invalid_bytes() {
+		let mut builder = Builder::with_options(&Options {
+			on_error: OnError::Skip,
+			..Default::default()
+		})
+		.unwrap();
+		builder.update(&r#"{ "a": [1, 2}, 3] }"#).unwrap();
+		builder.update(&"]").unwrap();
+
+		assert_eq!(builder.skipped(), &[12..13, 19..20]);
+		assert_eq!(builder.string().unwrap(), r#"{ "a": [1, 2, 3] }"#);
+
+		let mut builder = Builder::with_options(&Options {
+			on_error: OnError::Stop,
+			..Default::default()
+		})
+		.unwrap();
+		assert_eq!(
+			builder.update(&r#"{ "a": [1}"#).map_err(|e| e.kind()),
+			Err(ErrorKind::Invalid)
+		);
+		builder.update(&"]").unwrap();
+
+		// the rest of a rejected source is dropped along with the rejected byte, but still counts towards offsets.
+		assert_eq!(builder.update(&"x1}").unwrap_err().offset(), Some(11));
+		assert_eq!(builder.update(&"]").unwrap_err().offset(), Some(14));
+
+		assert!(builder.skipped().is_empty());
+		assert_eq!(builder.completed_string().unwrap(), r#"{ "a": [1]}"#);
+	}
+
+	#[test]
+	#[allow(clippy::single_range_in_vec_init)]
+	fn dialects_skip_or_stop_on_invalid_bytes() {
+		for dialect in [
+			Dialect::Json,
+			Dialect::Json5,
+			Dialect::Jsonc,
+			Dialect::Python,
+		] {
+			let options = |on_error| {
+				Options::builder()
+					.dialect(dialect)
+					.on_error(on_error)
+					.build()
+					.unwrap()
+			};
+
+			// a rejected byte leaves no trace, even if it was translated into several bytes or flushed a pending comma.
+			for (input, skipped, expected) in [
+				(r#"[1 'x, "z"]"#, &[3..5][..], r#"[1 , "z"]"#),
+				("[1,  :2]", &[5..6], "[1,  2]"),
+			] {
+				let mut builder = Builder::with_options(&options(OnError::Skip)).unwrap();
+				builder.update(&input).unwrap();
+
+				assert_eq!(builder.skipped(), skipped, "{dialect:?}");
+				assert_eq!(builder.completed_string().unwrap(), expected, "{dialect:?}");
+			}
+
+			let mut builder = Builder::with_options(&options(OnError::Stop)).unwrap();
+			assert_eq!(
+				builder.update(&"[1 'x").unwrap_err().offset(),
+				Some(3),
+				"{dialect:?}"
+			);
+			builder.update(&r#", "z"]"#).unwrap();
+			assert_eq!(
+				builder.completed_string().unwrap(),
+				r#"[1 , "z"]"#,
+				"{dialect:?}"
+			);
+		}
+	}
+
+	#[test]
+	fn reports_repairs() {
+		let mut builder = Builder::new();
+		builder.update(&r#"{ "a": "b", "c": [1, "d"#).unwrap();
+		assert_eq!(
+			builder.report().unwrap().repairs(),
+			&[
+				Repair::ClosedString { offset: 23 },
+				Repair::Closed {
+					offset: 24,
+					tokens: "]}".to_string()
+				},
+			]
+		);
+
+		builder.update(&r#""], "e"#).unwrap();
+		let report = builder.report().unwrap();
+		assert!(report.truncated());
+		assert_eq!(
+			report.repairs(),
+			&[
+				Repair::Truncated {
+					offset: 25,
+					length: 4
+				},
+				Repair::Closed {
+					offset: 25,
+					tokens: "}".to_string()
+				},
+			]
+		);
+
+		builder.update(&r#"": [] }"#).unwrap();
+		assert!(builder.report().unwrap().is_empty());
+	}
+
+	#[test]
+	fn emits_json_patches() {
+		let mut builder = Builder::new();
+		assert!(builder.patch().unwrap().is_empty());
+
+		builder.update(&r#"{ "a/b": [1, 2"#).unwrap();
+		assert_eq!(
+			builder.patch().unwrap().to_string(),
+			r#"[{"op":"add","path":"","value":{"a/b":[1,2]}}]"#
+		);
+		assert!(builder.patch().unwrap().is_empty());
+
+		builder.update(&r#"3, 4], "c": "\"x"#).unwrap();
+		assert_eq!(
+			builder.patch().unwrap().operations(),
+			&[
+				Operation::Replace {
+					path: "/a~1b/1".to_string(),
+					value: "23".to_string()
+				},
+				Operation::Add {
+					path: "/a~1b/2".to_string(),
+					value: "4".to_string()
+				},
+				Operation::Add {
+					path: "/c".to_string(),
+					value: r#""\"x""#.to_string()
+				},
+			]
+		);
+
+		builder.update(&r#"y", "d": 1."#).unwrap();
+		assert_eq!(
+			builder.patch().unwrap().operations(),
+			&[Operation::Replace {
+				path: "/c".to_string(),
+				value: r#""\"xy""#.to_string()
+			}]
+		);
+
+		// a repeated key replaces the value of the first occurrence.
+		let mut builder = Builder::new();
+		builder.update(&r#"{ "a": 1, "b": 2, "a": [3"#).unwrap();
+		assert_eq!(
+			builder.patch().unwrap().to_string(),
+			r#"[{"op":"add","path":"","value":{"a":[3],"b":2}}]"#
+		);
+
+		builder.update(&r#", 4], "a": 5"#).unwrap();
+		assert_eq!(
+			builder.patch().unwrap().operations(),
+			&[Operation::Replace {
+				path: "/a".to_string(),
+				value: "5".to_string()
+			}]
+		);
+	}
+
+	#[test]
+	fn formats_completed_output() {
+		let mut builder = Builder::new();
+		builder
+			.update(&"{ \"a\" : [ 1,\n\t{ }, \"b c\\\" [\"] , \"d\":{\"e\": [[], t")
+			.unwrap();
+
+		assert_eq!(
+			builder.clone().completed_minified().unwrap(),
+			r#"{"a":[1,{},"b c\" ["],"d":{"e":[[],true]}}"#
+		);
+		assert_eq!(
+			builder.completed_pretty("\t").unwrap(),
+			"{\n\t\"a\": [\n\t\t1,\n\t\t{},\n\t\t\"b c\\\" [\"\n\t],\n\t\"d\": {\n\t\t\"e\": [\n\t\t\t[],\n\t\t\ttrue\n\t\t]\n\t}\n}"
+		);
+
+		// leading whitespace is dropped, and doesn't cut off the object after it.
+		for (input, expected) in [
+			(" {", "{}"),
+			("\n{\"a\": [", r#"{"a":[]}"#),
+			("\r\n\t{\"a\"", "{}"),
+		] {
+			let mut builder = Builder::new();
+			builder.update(&input).unwrap();
+			assert_eq!(builder.completed_minified().unwrap(), expected);
+		}
+
+		// unbalanced input is never produced by a builder, but mustn't underflow the indentation either.
+		let mut output = Vec::new();
+		Formatter::pretty("\t").write(b"]}", &mut output);
+		assert_eq!(output, b"\n]\n}");
+	}
+
+	#[test]
+	fn validates_utf8() {
+		assert_eq!(
+			repair(r#"{ "name": "Miguél" }"#).unwrap(),
+			r#"{ "name": "Miguél" }"#
+		);
+		assert_eq!(repair(r#"{ "emoji": "🦀"#).unwrap(), r#"{ "emoji": "🦀"}"#);
+		assert_eq!(repair(r#"{ "🦀": 1 }"#).unwrap(), r#"{ "🦀": 1 }"#);
+
+		assert_eq!(
+			repair(b"{ \"a\": \"\xff\" }".as_slice()).map_err(|e| e.kind()),
+			Err(ErrorKind::Utf8)
+		);
+		assert_eq!(
+			repair(b"{ \"a\": \"\xc0\xaf\" }".as_slice()).map_err(|e| e.kind()),
+			Err(ErrorKind::Utf8)
+		);
+		assert_eq!(
+			repair(b"{ \"a\": \"\xed\xa0\x80\" }".as_slice()).map_err(|e| e.kind()),
+			Err(ErrorKind::Utf8)
+		);
+		assert_eq!(
+			repair(b"{ \"a\": \"\xf4\x90\x80\x80\" }".as_slice()).map_err(|e| e.kind()),
+			Err(ErrorKind::Utf8)
+		);
+		assert_eq!(
+			repair(b"{ \"a\": \"\xe2\x82\" }".as_slice()).map_err(|e| e.kind()),
+			Err(ErrorKind::Utf8)
+		);
+		assert_eq!(
+			repair(b"{ \"a\": \xe2\x82\xac }".as_slice()).map_err(|e| e.kind()),
+			Err(ErrorKind::Invalid)
+		);
+
+		let euro = "€".as_bytes();
+		for i in 1..euro.len() {
+			let mut input = br#"{ "a": "1"#.to_vec();
+			input.extend_from_slice(&euro[..i]);
+
+			assert_eq!(repair(input).unwrap(), r#"{ "a": "1"}"#);
+		}
+	}
+
+	#[test]
+	fn snapshots_are_valid_utf8_across_chunks() {
+		let input = r#"{ "text": "añ€🦀", "more": ["🦀"] }"#;
+		let mut builder = Builder::new();
+
+		for (i, character) in input.bytes().enumerate() {
+			builder.update(&character).unwrap();
+
+			let snapshot = builder.clone().completed_string().unwrap();
+			let text = snapshot.split('"').nth(3).unwrap_or_default();
+			assert!("añ€🦀".starts_with(text), "{i}: {snapshot}");
+
+			builder.clone().completed_pretty("  ").unwrap();
+			builder.patch().unwrap();
+		}
+
+		let mut builder = Builder::new();
+		builder
+			.update(&r#"{ "a": "€"#.as_bytes()[..9].to_vec())
+			.unwrap();
+		assert_eq!(
+			builder.report().unwrap().repairs(),
+			&[
+				Repair::TrimmedCharacter {
+					offset: 8,
+					length: 1
+				},
+				Repair::ClosedString { offset: 8 },
+				Repair::Closed {
+					offset: 9,
+					tokens: "}".to_string()
+				},
+			]
+		);
+		assert_eq!(builder.completed_string().unwrap(), r#"{ "a": ""}"#);
+	}
+
+	#[test]
+	fn handles_surrogate_pairs() {
+		assert_eq!(repair(r#"{ "a": "x🦀"#).unwrap(), r#"{ "a": "x🦀"}"#);
+		assert_eq!(repair(r#"{ "a": "x\ud83e"#).unwrap(), r#"{ "a": "x"}"#);
+		assert_eq!(repair(r#"{ "a": "x\ud83e\"#).unwrap(), "{}");
+		assert_eq!(
+			repair(r#"{ "a": "x\ud83e", "b": "\udd80"#).unwrap(),
+			r#"{ "a": "x\ud83e", "b": "\udd80"}"#
+		);
+
+		let options = Options {
+			reject_unpaired_surrogates: true,
+			..Default::default()
+		};
+		for unpaired in [
+			r#"{ "a": "\ud83e" }"#,
+			r#"{ "a": "\ud83ex" }"#,
+			r#"{ "a": "\ud83e\n" }"#,
+			r#"{ "a": "\ud83e\ud83e" }"#,
+			r#"{ "a": "\udd80" }"#,
+		] {
+			let mut verifier = Verifier::with_options(&options).unwrap();
+			let result = unpaired.bytes().try_for_each(|c| verifier.update(c));
+
+			assert_eq!(
+				result.map_err(|e| e.kind()),
+				Err(ErrorKind::Invalid),
+				"{unpaired}"
+			);
+		}
+
+		let mut builder = Builder::with_options(&options).unwrap();
+		builder.update(&r#"{ "a": "🦀é", "b": "\ud83e"#).unwrap();
+		assert_eq!(
+			builder.completed_string().unwrap(),
+			r#"{ "a": "🦀é", "b": ""}"#
+		);
+	}
+
+	#[test]
+	fn stack_verifier_matches_verifier() {
+		let input = br#"{ "a": [1, { "b": [true, "\ud83e\udd80"] }], "c": null }"#;
+
+		let mut verifier = Verifier::new();
+		let mut stack = StackVerifier::<4>::new();
+		for character in input {
+			assert_eq!(stack.update(*character), verifier.update(*character));
+			assert_eq!(stack.status(), verifier.status());
+		}
+		assert_eq!(stack.status(), Status::Valid);
+		assert_eq!(stack.len(), input.len());
+
+		let mut shallow = StackVerifier::<3>::new();
+		let result = input.iter().try_for_each(|c| shallow.update(*c));
+		assert_eq!(result.map_err(|e| e.kind()), Err(ErrorKind::Exceeded));
+
+		// a failed update leaves the stack untouched, so the document can still be closed.
+		assert_eq!(shallow.update(b'"'), Ok(()));
+		assert!(shallow.accepts(br#""}]}"#));
+
+		let mut limited = StackVerifier::<4>::with_options(&Options {
+			maximum_depth: 1,
+			..Default::default()
+		})
+		.unwrap();
+		assert_eq!(limited.update(b'['), Ok(()));
+		assert_eq!(
+			limited.update(b'[').map_err(|e| e.kind()),
+			Err(ErrorKind::Exceeded)
+		);
+	}
+
+	#[test]
+	fn bulk_string_updates_match_byte_updates() {
+		let input = "{ \"text\": \"a long string body with \\\"escapes\\\", \\u00e9 and multi-byte 🦀 characters é\", \"n\": [1, \"x\"] }";
+
+		for chunk_size in [1, 3, 7, 8, 9, 16, input.len()] {
+			let mut builder = Builder::new();
+			for chunk in input.as_bytes().chunks(chunk_size) {
+				builder.update(&chunk).unwrap();
+			}
+			assert_eq!(builder.clone().string().unwrap(), input);
+			assert_eq!(builder.len(), input.len());
+		}
+
+		for invalid in [
+			&b"[\"tab\tin string\"]"[..],
+			b"[\"bad \xff byte\"]",
+			b"[\"overlong \xc0\xaf\"]",
+		] {
+			let mut chunked = Builder::new();
+			let mut bytewise = Builder::new();
+
+			assert!(chunked.update(&invalid).is_err());
+			for character in invalid {
+				if bytewise.update(character).is_err() {
+					break;
+				}
+			}
+			assert_eq!(chunked.len(), bytewise.len());
+		}
+	}
+
+	#[test]
+	fn snapshots_match_completed_bytes() {
+		let input = r#"{ "a": [1, {"b": true, "c": "d\u00e9🦀"}], "e": nul"#;
+
+		let mut builder = Builder::new();
+		for (i, character) in input.bytes().enumerate() {
+			builder.update(&character).unwrap();
+
+			let snapshot = <[&[u8]; 2]>::from(builder.snapshot().unwrap()).concat();
+			assert_eq!(snapshot, builder.clone().completed_bytes().unwrap(), "{i}");
+		}
+
+		builder.update(&"l}").unwrap();
+		let completed = builder.clone().bytes().unwrap();
+		assert_eq!(builder.snapshot().unwrap(), (&completed[..], &b""[..]));
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn writes_vectored_snapshots() {
+		use std::io::Write;
+
+		let mut builder = Builder::new();
+		builder.update(&r#"[{"a": "b"}, {"c": tr"#).unwrap();
+
+		let slices = builder.io_slices().unwrap();
+		assert_eq!(&*slices[0], br#"[{"a": "b"}, {"c": tr"#);
+		assert_eq!(&*slices[1], b"ue}]");
+
+		let mut output = vec![];
+		let written = output.write_vectored(&slices).unwrap();
+		assert_eq!(written, output.len());
+		assert_eq!(output, builder.clone().completed_bytes().unwrap());
+	}
+
+	#[test]
+	#[allow(clippy::single_range_in_vec_init)]
+	fn enforces_resource_limits() {
+		let check = |options: Options, input: &str| {
+			let mut verifier = Verifier::with_options(&options).unwrap();
+			input
+				.bytes()
+				.try_for_each(|c| verifier.update(c))
+				.map_err(|e| e.kind())
+		};
+
+		let length = || Options {
+			maximum_length: 10,
+			..Default::default()
+		};
+		assert_eq!(check(length(), r#"["abcdef"]"#), Ok(()));
+		assert_eq!(check(length(), r#"["abcdefg"]"#), Err(ErrorKind::TooLong));
+
+		let strings = || Options {
+			maximum_string_length: 4,
+			..Default::default()
+		};
+		assert_eq!(check(strings(), r#"{"abcd": "\n\t"}"#), Ok(()));
+		assert_eq!(
+			check(strings(), r#"{"abcde": 1}"#),
+			Err(ErrorKind::StringTooLong)
+		);
+		assert_eq!(
+			check(strings(), r#"["\u00e9"]"#),
+			Err(ErrorKind::StringTooLong)
+		);
+
+		let keys = || Options {
+			maximum_keys_per_object: 2,
+			..Default::default()
+		};
+		assert_eq!(check(keys(), r#"{"a": {"c": 1, "d": 2}, "b": []}"#), Ok(()));
+		assert_eq!(
+			check(keys(), r#"{"a": 1, "b": 2, "c"#),
+			Err(ErrorKind::TooManyKeys)
+		);
+
+		let arrays = || Options {
+			maximum_array_length: 2,
+			..Default::default()
+		};
+		assert_eq!(
+			check(arrays(), r#"[[1, 2], [ ], {"a": [3]}]"#),
+			Err(ErrorKind::ArrayTooLong)
+		);
+		assert_eq!(check(arrays(), r#"[[1, 2], {"a": [3, [4]]}]"#), Ok(()));
+
+		// limits also apply to strings copied over in bulk, and a failed update can be followed by a valid one.
+		let mut builder = Builder::with_options(&Options {
+			on_error: OnError::Stop,
+			..strings()
+		})
+		.unwrap();
+		assert_eq!(
+			builder.update(&r#"["abcdefgh"#).map_err(|e| e.kind()),
+			Err(ErrorKind::StringTooLong)
+		);
+		assert_eq!(builder.update(&"\"]"), Ok(()));
+		assert_eq!(builder.completed_string().unwrap(), r#"["abcd"]"#);
+
+		// every byte past the maximum length is dropped, which is recorded as a single range.
+		let mut builder = Builder::with_options(&Options {
+			on_error: OnError::Skip,
+			..length()
+		})
+		.unwrap();
+		builder.update(&r#"["abcdef"], "gh"#).unwrap();
+		let checkpoint = builder.checkpoint();
+		builder.update(&r#"ijkl""#).unwrap();
+		assert_eq!(builder.skipped(), &[10..20]);
+
+		builder.rollback(checkpoint).unwrap();
+		assert_eq!(builder.skipped(), &[10..15]);
+		assert_eq!(builder.completed_string().unwrap(), r#"["abcdef"]"#);
+	}
+
+	#[test]
+	fn rejects_invalid_options() {
+		assert_eq!(
+			Verifier::with_maximum_depth(0).unwrap_err(),
+			ConfigError::ZeroMaximumDepth
+		);
+		assert_eq!(
+			Builder::with_maximum_depth(0).unwrap_err(),
+			ConfigError::ZeroMaximumDepth
+		);
+
+		let options = Options {
+			maximum_length: 0,
+			..Default::default()
+		};
+		assert_eq!(options.validate(), Err(ConfigError::ZeroMaximumLength));
+		assert_eq!(
+			StackVerifier::<8>::with_options(&options).unwrap_err(),
+			ConfigError::ZeroMaximumLength
+		);
+
+		let options = Options::builder()
+			.maximum_depth(2)
+			.maximum_array_length(1)
+			.on_error(OnError::Stop)
+			.build()
+			.unwrap();
+		let mut builder = Builder::with_options(&options).unwrap();
+		assert_eq!(
+			builder.update(&"[[1], [[2]]]").map_err(|e| e.kind()),
+			Err(ErrorKind::ArrayTooLong)
+		);
+		assert_eq!(builder.completed_string().unwrap(), "[[1]]");
+	}
+
+	#[test]
+	fn describes_errors() {
+		let mut verifier = Verifier::new();
+		let result = b"[1, 2}".iter().try_for_each(|c| verifier.update(*c));
+		let error = result.unwrap_err();
+		assert_eq!(
+			(error.kind(), error.offset(), error.path()),
+			(ErrorKind::Invalid, Some(5), None)
+		);
+		assert_eq!(
+			error.to_string(),
+			"The input stream is not valid JSON. (at byte 5)"
+		);
+		assert_eq!(verifier.update(b':').unwrap_err().offset(), Some(5));
+
+		let mut builder = Builder::with_options(&Options {
+			on_error: OnError::Stop,
+			..Default::default()
+		})
+		.unwrap();
+		builder
+			.update(&"{\n  \"a/b\": [{\"c\": 1}, {\"d~\": \"é\"")
+			.unwrap();
+		let error = builder.update(&"]").unwrap_err();
+		assert_eq!(error.offset(), Some(33));
+		assert_eq!(error.path(), Some("/a~1b/1/d~0"));
+		assert_eq!(error.snippet(), Some(r#"  "a/b": [{"c": 1}, {"d~": "é"]"#));
+		assert_eq!(
+			error.to_string(),
+			r#"The input stream is not valid JSON. (at byte 33, path /a~1b/1/d~0)
+    "a/b": [{"c": 1}, {"d~": "é"]
+                                ^"#
+		);
+
+		// `OnError::Stop` drops the rejected byte, but later offsets still count it.
+		let error = builder.update(&b"\n}\xff".as_slice()).unwrap_err();
+		assert_eq!(
+			(error.kind(), error.offset()),
+			(ErrorKind::Invalid, Some(36))
+		);
+		assert_eq!(error.path(), Some("/a~1b/1"));
+
+		// a poisoned builder rejects everything, so there's nothing to point at.
+		let mut builder = Builder::new();
+		assert!(builder.update(&"}").is_err());
+		let error = builder.update(&"[").unwrap_err();
+		assert_eq!(error.offset(), None);
+		assert_eq!(error.to_string(), "The input stream is not valid JSON.");
+	}
+
+	#[test]
+	fn replaces_invalid_utf8_lossily() {
+		let options = Options::builder().allow_invalid_utf8(true).build().unwrap();
+		let input = b"[\"a\xffb\", \"\xe2\x82c\", \"\xc0\xaf\", \"\xff\xe2\x82\xac\xf0\x9f\xa6";
+
+		for chunk_size in [1, 3, input.len()] {
+			let mut builder = Builder::with_options(&options).unwrap();
+			for chunk in input.chunks(chunk_size) {
+				builder.update(&chunk).unwrap();
+			}
+
+			assert_eq!(
+				builder.clone().completed_string().unwrap_err().kind(),
+				ErrorKind::Utf8
+			);
+			assert_eq!(
+				builder.completed_string_lossy().unwrap(),
+				"[\"a\u{FFFD}b\", \"\u{FFFD}c\", \"\u{FFFD}\u{FFFD}\", \"\u{FFFD}€\"]"
+			);
+		}
+
+		// invalid bytes are still only allowed inside strings.
+		let mut builder = Builder::with_options(&options).unwrap();
+		assert_eq!(
+			builder.update(&b"[\xff".as_slice()).unwrap_err().kind(),
+			ErrorKind::Invalid
+		);
+	}
+
+	#[test]
+	#[allow(clippy::single_range_in_vec_init)]
+	fn skips_byte_order_marks() {
+		let input = "\u{FEFF}{ \"crab\": [\"🦀\", \"𝄞\"";
+		let expected = "{ \"crab\": [\"🦀\", \"𝄞\"]}";
+
+		for chunk_size in [1, 2, 3, input.len()] {
+			let mut builder = Builder::new();
+			for chunk in input.as_bytes().chunks(chunk_size) {
+				builder.update(&chunk).unwrap();
+			}
+			assert_eq!(builder.completed_string().unwrap(), expected);
+		}
+
+		// a partial byte order mark is rejected on its own, and the byte that cut it short is applied as usual.
+		let mut builder = Builder::new();
+		let error = builder.update(&b"\xEF\xBB{".as_slice()).unwrap_err();
+		assert_eq!(
+			(error.kind(), error.offset()),
+			(ErrorKind::Invalid, Some(0))
+		);
+
+		let skip = Options::builder().on_error(OnError::Skip).build().unwrap();
+		let mut builder = Builder::with_options(&skip).unwrap();
+		builder.update(&b"\xEF".as_slice()).unwrap();
+		builder.update(&r#"{"a": 1}"#).unwrap();
+		assert_eq!(builder.skipped(), &[0..1]);
+		assert_eq!(builder.string().unwrap(), r#"{"a": 1}"#);
+
+		let stop = Options::builder().on_error(OnError::Stop).build().unwrap();
+		let mut builder = Builder::with_options(&stop).unwrap();
+		let error = builder.update(&b"\xEF\xBB[1".as_slice()).unwrap_err();
+		assert_eq!(error.offset(), Some(0));
+		builder.update(&"[1").unwrap();
+		assert_eq!(builder.completed_string().unwrap(), "[1]");
+
+		for endianness in [Endianness::Little, Endianness::Big] {
+			let utf16 = input.encode_utf16().flat_map(|unit| match endianness {
+				Endianness::Little => unit.to_le_bytes(),
+				Endianness::Big => unit.to_be_bytes(),
+			});
+			let utf16 = utf16.collect::<Vec<_>>();
+
+			for chunk_size in [1, 3, 4, 5, utf16.len()] {
+				let mut decoder = Utf16::new();
+				let mut builder = Builder::new();
+				for chunk in utf16.chunks(chunk_size) {
+					builder.update(&decoder.transcode(chunk)).unwrap();
+				}
+				assert_eq!(builder.completed_string().unwrap(), expected);
+			}
+		}
+
+		let mut decoder = Utf16::with_endianness(Endianness::Big);
+		assert_eq!(
+			decoder.transcode(b"\x00\"\xD8\x3D\x00\"\xDC\x00"),
+			"\"\u{FFFD}\"\u{FFFD}".as_bytes()
+		);
+
+		// whatever is still held back when the input ends is replaced, so it's never lost.
+		assert_eq!(decoder.transcode(b"\xD8\x3D\x00"), b"");
+		assert_eq!(decoder.finish(), "\u{FFFD}\u{FFFD}".as_bytes());
+		assert_eq!(decoder.finish(), b"");
+	}
+
+	#[test]
+	fn translates_json5() {
+		let options = Options::builder().dialect(Dialect::Json5).build().unwrap();
+		let input = r#"// config
+{
+	unquoted: 'single "quoted" \'string\'',
+	$key_2: [0x1F, -0XFF, +1, .5, 5., -.5e3, Infinity, -NaN, /* inline */ 0, 0.25,],
+	"escapes": 'a\x41\v\0\
+b',
+}"#;
+		let expected = r#"
+{
+	"unquoted": "single \"quoted\" 'string'",
+	"$key_2": [31, -255, 1, 0.5, 5.0, -0.5e3, null, null,   0, 0.25],
+	"escapes": "a\u0041\u000b\u0000b"
+}"#;
+
+		for chunk_size in [1, 2, 7, input.len()] {
+			let mut builder = Builder::with_options(&options).unwrap();
+			for chunk in input.as_bytes().chunks(chunk_size) {
+				builder.update(&chunk).unwrap();
+			}
+			assert_eq!(builder.completed_string().unwrap(), expected);
+		}
+
+		for (input, expected) in [
+			("{ a: [1, 2,", r#"{ "a": [1, 2]}"#),
+			("{ a: 'b", r#"{ "a": "b"}"#),
+			("{ ke", "{}"),
+			("[0x1F", "[]"),
+			("[Infin", "[null]"),
+			("[1, /* a comment", "[1]"),
+			("[1 /", "[1 ]"),
+		] {
+			let mut builder = Builder::with_options(&options).unwrap();
+			builder.update(&input).unwrap();
+			assert_eq!(builder.completed_string().unwrap(), expected);
+		}
+
+		for input in [
+			"[0x]",
+			"[0x1.5]",
+			"[Infinite]",
+			"[1 / 2]",
+			"{ a: +}",
+			r"['\1']",
+		] {
+			let mut builder = Builder::with_options(&options).unwrap();
+			assert_eq!(
+				builder.update(&input).unwrap_err().kind(),
+				ErrorKind::Invalid,
+				"{input}"
+			);
+		}
+	}
+
+	#[test]
+	fn strips_jsonc_comments() {
+		let options = Options::builder().dialect(Dialect::Jsonc).build().unwrap();
+		let input = r#"{
+	// the output directory
+	"outDir": "dist", /* not a // comment */
+	"include": ["src/**/*.ts", "http://example.com"] /* trailing */
+}"#;
+		let expected = "{\n\t\n\t\"outDir\": \"dist\",  \n\t\"include\": [\"src/**/*.ts\", \"http://example.com\"]  \n}";
+
+		for chunk_size in [1, 2, 7, input.len()] {
+			let mut builder = Builder::with_options(&options).unwrap();
+			for chunk in input.as_bytes().chunks(chunk_size) {
+				builder.update(&chunk).unwrap();
+			}
+			assert_eq!(builder.completed_string().unwrap(), expected);
+		}
+
+		for (input, expected) in [
+			(r#"{ "a": 1, /* dangling"#, r#"{ "a": 1}"#),
+			(r#"{ "a": 1 /* dangling *"#, r#"{ "a": 1 }"#),
+			(r#"{ "a": ["b", // dangling"#, r#"{ "a": ["b"]}"#),
+			(r#"{ "a": "b" /"#, r#"{ "a": "b" }"#),
+			(r#"{ "a": "b /* c"#, r#"{ "a": "b /* c"}"#),
+		] {
+			let mut builder = Builder::with_options(&options).unwrap();
+			builder.update(&input).unwrap();
+			assert_eq!(builder.completed_string().unwrap(), expected);
+		}
+
+		// everything other than comments must still be strict JSON.
+		for input in ["{ a: 1 }", "['a']", "[1, 2,]", r#"["\x41"]"#, "[1 / 2]"] {
+			let mut builder = Builder::with_options(&options).unwrap();
+			assert_eq!(
+				builder.update(&input).unwrap_err().kind(),
+				ErrorKind::Invalid,
+				"{input}"
+			);
+		}
+	}
+
+	#[test]
+	fn converts_python_literals() {
+		let options = Options::builder().dialect(Dialect::Python).build().unwrap();
+		let input = r#"{'name': 'Miguel', 'quote': 'say "hi"', 'it\'s': "isn't", 'flags': (True, False, None), 'point': (1,), 'empty': (), 'escaped': '\x41\n\a\101\0\U0001F600\U000000e9'}"#;
+		let expected = r#"{"name": "Miguel", "quote": "say \"hi\"", "it's": "isn't", "flags": [true, false, null], "point": [1], "empty": [], "escaped": "\u0041\n\u0007\u0041\u0000\ud83d\ude00\u00e9"}"#;
+
+		for chunk_size in [1, 2, 7, input.len()] {
+			let mut builder = Builder::with_options(&options).unwrap();
+			for chunk in input.as_bytes().chunks(chunk_size) {
+				builder.update(&chunk).unwrap();
+			}
+			assert_eq!(builder.completed_string().unwrap(), expected);
+		}
+
+		for (input, expected) in [
+			("{'a': Tr", r#"{"a": true}"#),
+			("{'a': (Fals", r#"{"a": [false]}"#),
+			("[No", "[null]"),
+			("['it\\'s", r#"["it's"]"#),
+			(r"['\U0001F6", r#"[""]"#),
+			(r"['\1", r#"[""]"#),
+			(r"['\12a", r#"["\u000aa"]"#),
+			("[(1, 2), ('a',", r#"[[1, 2], ["a"]]"#),
+			("([1, (2,)], {'a': ()}", r#"[[1, [2]], {"a": []}]"#),
+		] {
+			let mut builder = Builder::with_options(&options).unwrap();
+			builder.update(&input).unwrap();
+			assert_eq!(builder.completed_string().unwrap(), expected);
+		}
+
+		// a tuple is closed with `)` and a list with `]`, however deeply they're nested.
+		for input in [
+			"[Nope]",
+			"{True: 1}",
+			"[1 // 2]",
+			"(1]",
+			"[1)",
+			"([1), 2)",
+			"{'a': (1}",
+			")",
+			r"['\q']",
+			r"['\/']",
+			r"['\N{BULLET}']",
+			r"['\U0001F60G']",
+			r"['\U00110000']",
+		] {
+			let mut builder = Builder::with_options(&options).unwrap();
+			assert_eq!(
+				builder.update(&input).unwrap_err().kind(),
+				ErrorKind::Invalid,
+				"{input}"
+			);
+		}
+	}
 }
@@ -0,0 +1,230 @@
+/// How a [`Builder`](crate::Builder) reacts to bytes that would make the underlying JSON object invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnError {
+	/// Drop the offending byte, record its offset and continue with the rest of the input.
+	Skip,
+
+	/// Stop applying the current input and return the error, keeping everything that was applied before it.
+	Stop,
+
+	/// Return the error and mark the `Builder` as permanently invalid.
+	#[default]
+	Poison,
+}
+
+/// The syntax a [`Builder`](crate::Builder) accepts, which is translated into strict JSON as it's received.
+///
+/// Verifiers always expect strict JSON, regardless of the dialect in their options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+	/// Strict JSON, as described in [RFC 8259](https://datatracker.ietf.org/doc/html/rfc8259).
+	#[default]
+	Json,
+
+	/// [JSON5](https://spec.json5.org): comments, trailing commas, single-quoted strings, unquoted keys, hexadecimal
+	/// numbers, `Infinity` and `NaN` (written as `null`), explicit `+` signs and leading or trailing decimal points.
+	Json5,
+
+	/// JSON with `//` and `/* */` comments, as used by editor and `tsconfig.json` configuration files. Comments are
+	/// removed, including one that is cut off at the end of the input.
+	Jsonc,
+
+	/// Python literals, as printed by `repr()` for dicts, lists and tuples: `True`, `False` and `None`, single-quoted
+	/// strings (with Python escapes), tuples (written as arrays) and trailing commas. Escapes Python doesn't define are
+	/// rejected, and so are `\N{...}` escapes, since character names can't be looked up.
+	Python,
+}
+
+/// Expanded options for constructing a [`Builder`](crate::Builder) or verifier instance.
+///
+/// Options can be built field by field, or through [`Options::builder`], which validates them up front.
+#[derive(Debug, Clone)]
+pub struct Options {
+	/// The maximum nesting depth of arrays and objects, which must be at least `1`. Defaults to no limit (a
+	/// [`StackVerifier`](crate::StackVerifier) is also limited by its `DEPTH`). Going deeper raises
+	/// [`ErrorKind::Exceeded`](crate::ErrorKind::Exceeded).
+	pub maximum_depth: usize,
+	/// The maximum number of bytes in the whole JSON object, including whitespace, which must be at least `1`. Defaults
+	/// to no limit. Any byte past it raises [`ErrorKind::TooLong`](crate::ErrorKind::TooLong).
+	pub maximum_length: usize,
+	/// The maximum number of bytes in a single string (or key), as written in the input including escape sequences
+	/// but not the quotes around it. Defaults to no limit. A longer string raises
+	/// [`ErrorKind::StringTooLong`](crate::ErrorKind::StringTooLong).
+	pub maximum_string_length: usize,
+	/// The maximum number of keys in a single object, not counting those of the objects nested in it. Defaults to no
+	/// limit. Another key raises [`ErrorKind::TooManyKeys`](crate::ErrorKind::TooManyKeys).
+	pub maximum_keys_per_object: usize,
+	/// The maximum number of items in a single array, not counting those of the arrays nested in it. Defaults to no
+	/// limit. Another item raises [`ErrorKind::ArrayTooLong`](crate::ErrorKind::ArrayTooLong).
+	pub maximum_array_length: usize,
+	/// The number of bytes a [`Builder`](crate::Builder) allocates for its JSON object up front. Defaults to `512`.
+	pub initial_capacity: usize,
+	/// How a [`Builder`](crate::Builder) reacts to bytes that would make its JSON object invalid, including those
+	/// that exceed a limit above. Defaults to [`OnError::Poison`].
+	pub on_error: OnError,
+	/// Reject `\uXXXX` escapes for UTF-16 surrogates that are not part of a high-low pair, which many parsers refuse.
+	pub reject_unpaired_surrogates: bool,
+	/// Accept invalid UTF-8 inside strings instead of rejecting it, so it can be replaced with U+FFFD by
+	/// [`Builder::completed_string_lossy`](crate::Builder::completed_string_lossy).
+	pub allow_invalid_utf8: bool,
+	/// The syntax of the input, which a [`Builder`](crate::Builder) translates into strict JSON.
+	pub dialect: Dialect,
+}
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			initial_capacity: 512,
+			maximum_depth: usize::MAX,
+			maximum_length: usize::MAX,
+			maximum_string_length: usize::MAX,
+			maximum_keys_per_object: usize::MAX,
+			maximum_array_length: usize::MAX,
+			on_error: OnError::default(),
+			reject_unpaired_surrogates: false,
+			allow_invalid_utf8: false,
+			dialect: Dialect::Json,
+		}
+	}
+}
+
+impl Options {
+	/// Returns a builder for `Options`, starting from the defaults.
+	///
+	/// # Example
+	/// ```
+	/// # use repair_json::{ConfigError, OnError, Options, StackVerifier};
+	/// let options = Options::builder()
+	///     .maximum_depth(64)
+	///     .maximum_length(1 << 20)
+	///     .on_error(OnError::Skip)
+	///     .build()
+	///     .unwrap();
+	/// assert!(StackVerifier::<64>::with_options(&options).is_ok());
+	///
+	/// assert_eq!(Options::builder().maximum_depth(0).build().unwrap_err(), ConfigError::ZeroMaximumDepth);
+	/// ```
+	pub fn builder() -> OptionsBuilder {
+		OptionsBuilder::default()
+	}
+
+	/// Checks that these options can be used to construct a [`Builder`](crate::Builder) or verifier.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `maximum_depth` or `maximum_length` is `0`.
+	pub const fn validate(&self) -> Result<(), ConfigError> {
+		if self.maximum_depth == 0 {
+			Err(ConfigError::ZeroMaximumDepth)
+		} else if self.maximum_length == 0 {
+			Err(ConfigError::ZeroMaximumLength)
+		} else {
+			Ok(())
+		}
+	}
+}
+
+/// A builder for [`Options`], created by [`Options::builder`].
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct OptionsBuilder {
+	options: Options,
+}
+
+impl OptionsBuilder {
+	/// Sets the maximum nesting depth of arrays and objects, which must be at least `1`.
+	pub const fn maximum_depth(mut self, maximum_depth: usize) -> Self {
+		self.options.maximum_depth = maximum_depth;
+		self
+	}
+
+	/// Sets the maximum number of bytes in the whole JSON object, which must be at least `1`.
+	pub const fn maximum_length(mut self, maximum_length: usize) -> Self {
+		self.options.maximum_length = maximum_length;
+		self
+	}
+
+	/// Sets the maximum number of bytes in a single string (or key).
+	pub const fn maximum_string_length(mut self, maximum_string_length: usize) -> Self {
+		self.options.maximum_string_length = maximum_string_length;
+		self
+	}
+
+	/// Sets the maximum number of keys in a single object.
+	pub const fn maximum_keys_per_object(mut self, maximum_keys_per_object: usize) -> Self {
+		self.options.maximum_keys_per_object = maximum_keys_per_object;
+		self
+	}
+
+	/// Sets the maximum number of items in a single array.
+	pub const fn maximum_array_length(mut self, maximum_array_length: usize) -> Self {
+		self.options.maximum_array_length = maximum_array_length;
+		self
+	}
+
+	/// Sets the number of bytes a [`Builder`](crate::Builder) reserves up front.
+	pub const fn initial_capacity(mut self, initial_capacity: usize) -> Self {
+		self.options.initial_capacity = initial_capacity;
+		self
+	}
+
+	/// Sets how a [`Builder`](crate::Builder) reacts to bytes that would make the JSON object invalid.
+	pub const fn on_error(mut self, on_error: OnError) -> Self {
+		self.options.on_error = on_error;
+		self
+	}
+
+	/// Sets whether `\uXXXX` escapes for unpaired UTF-16 surrogates are rejected.
+	pub const fn reject_unpaired_surrogates(mut self, reject_unpaired_surrogates: bool) -> Self {
+		self.options.reject_unpaired_surrogates = reject_unpaired_surrogates;
+		self
+	}
+
+	/// Sets whether invalid UTF-8 inside strings is accepted.
+	pub const fn allow_invalid_utf8(mut self, allow_invalid_utf8: bool) -> Self {
+		self.options.allow_invalid_utf8 = allow_invalid_utf8;
+		self
+	}
+
+	/// Sets the syntax of the input.
+	pub const fn dialect(mut self, dialect: Dialect) -> Self {
+		self.options.dialect = dialect;
+		self
+	}
+
+	/// Validates and returns the options.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the options are invalid, as described in [`Options::validate`].
+	pub const fn build(self) -> Result<Options, ConfigError> {
+		match self.options.validate() {
+			Ok(()) => Ok(self.options),
+			Err(e) => Err(e),
+		}
+	}
+}
+
+/// Errors caused by invalid [`Options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum ConfigError {
+	/// The maximum depth is `0`, which wouldn't allow any JSON object.
+	#[cfg_attr(feature = "std", error("The maximum depth must be at least 1."))]
+	ZeroMaximumDepth,
+
+	/// The maximum length is `0`, which wouldn't allow any JSON object.
+	#[cfg_attr(feature = "std", error("The maximum length must be at least 1."))]
+	ZeroMaximumLength,
+}
+
+// `thiserror` needs `std`, so without it we only provide `Display`.
+#[cfg(not(feature = "std"))]
+impl core::fmt::Display for ConfigError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.write_str(match self {
+			Self::ZeroMaximumDepth => "The maximum depth must be at least 1.",
+			Self::ZeroMaximumLength => "The maximum length must be at least 1.",
+		})
+	}
+}
@@ -0,0 +1,478 @@
+use alloc::{
+	format,
+	string::{String, ToString},
+	vec,
+	vec::Vec,
+};
+use core::fmt::{self, Display, Write};
+
+use crate::ErrorKind;
+
+/// A single JSON Patch ([RFC 6902](https://datatracker.ietf.org/doc/html/rfc6902)) operation.
+///
+/// Paths are JSON Pointers ([RFC 6901](https://datatracker.ietf.org/doc/html/rfc6901)), and values are serialized JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+	/// Adds `value` at `path`.
+	Add { path: String, value: String },
+
+	/// Replaces the value at `path` with `value`.
+	Replace { path: String, value: String },
+
+	/// Removes the value at `path`.
+	Remove { path: String },
+}
+
+impl Display for Operation {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let (op, path, value) = match self {
+			Self::Add { path, value } => ("add", path, Some(value)),
+			Self::Replace { path, value } => ("replace", path, Some(value)),
+			Self::Remove { path } => ("remove", path, None),
+		};
+
+		write!(f, r#"{{"op":"{op}","path":"#)?;
+		write_string(f, path)?;
+
+		if let Some(value) = value {
+			write!(f, r#","value":{value}"#)?;
+		}
+
+		f.write_char('}')
+	}
+}
+
+/// A list of JSON Patch operations, as returned by [`Builder::patch`](crate::Builder::patch).
+///
+/// The [`Display`] implementation renders the patch as a JSON array, ready to be sent to a client.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Patch {
+	operations: Vec<Operation>,
+}
+
+impl Patch {
+	/// Returns the operations in this patch, in the order they must be applied.
+	#[must_use]
+	pub fn operations(&self) -> &[Operation] {
+		&self.operations
+	}
+
+	/// Returns `true` if the document did not change.
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.operations.is_empty()
+	}
+}
+
+impl Display for Patch {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_char('[')?;
+
+		for (i, operation) in self.operations.iter().enumerate() {
+			if i > 0 {
+				f.write_char(',')?;
+			}
+
+			write!(f, "{operation}")?;
+		}
+
+		f.write_char(']')
+	}
+}
+
+/// A minimal JSON document tree, used to compute the difference between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+	/// The serialized form of a string, number or literal.
+	Scalar(String),
+	Array(Vec<Self>),
+	Object(Vec<(String, Self)>),
+}
+
+impl Value {
+	/// Parses a complete JSON document, returning `None` if it only contains whitespace.
+	pub fn parse(data: &[u8]) -> Result<Option<Self>, ErrorKind> {
+		let mut parser = Parser { data, position: 0 };
+
+		parser.skip_whitespace();
+		if parser.position == data.len() {
+			return Ok(None);
+		}
+
+		parser.value().map(Some)
+	}
+
+	/// Returns the operations needed to turn `previous` into `self`.
+	pub fn diff(&self, previous: Option<&Self>) -> Patch {
+		let mut operations = vec![];
+
+		match previous {
+			Some(previous) => diff(previous, self, &mut String::new(), &mut operations),
+			None => operations.push(Operation::Add {
+				path: String::new(),
+				value: self.to_string(),
+			}),
+		}
+
+		Patch { operations }
+	}
+}
+
+impl Display for Value {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Scalar(raw) => f.write_str(raw),
+			Self::Array(items) => {
+				f.write_char('[')?;
+				for (i, item) in items.iter().enumerate() {
+					if i > 0 {
+						f.write_char(',')?;
+					}
+					write!(f, "{item}")?;
+				}
+				f.write_char(']')
+			},
+			Self::Object(entries) => {
+				f.write_char('{')?;
+				for (i, (key, value)) in entries.iter().enumerate() {
+					if i > 0 {
+						f.write_char(',')?;
+					}
+					write_string(f, key)?;
+					write!(f, ":{value}")?;
+				}
+				f.write_char('}')
+			},
+		}
+	}
+}
+
+fn diff(previous: &Value, current: &Value, path: &mut String, operations: &mut Vec<Operation>) {
+	if previous == current {
+		return;
+	}
+
+	match (previous, current) {
+		(Value::Array(previous), Value::Array(current)) => {
+			for (i, (previous, current)) in previous.iter().zip(current).enumerate() {
+				with_segment(path, &i.to_string(), |path| {
+					diff(previous, current, path, operations);
+				});
+			}
+
+			for (i, item) in current.iter().enumerate().skip(previous.len()) {
+				operations.push(Operation::Add {
+					path: format!("{path}/{i}"),
+					value: item.to_string(),
+				});
+			}
+
+			for i in (current.len()..previous.len()).rev() {
+				operations.push(Operation::Remove {
+					path: format!("{path}/{i}"),
+				});
+			}
+		},
+		(Value::Object(previous), Value::Object(current)) => {
+			for (key, _) in previous {
+				if !current.iter().any(|(k, _)| k == key) {
+					with_segment(path, key, |path| {
+						operations.push(Operation::Remove { path: path.clone() });
+					});
+				}
+			}
+
+			for (key, value) in current {
+				with_segment(path, key, |path| {
+					match previous.iter().find(|(k, _)| k == key) {
+						Some((_, previous)) => diff(previous, value, path, operations),
+						None => operations.push(Operation::Add {
+							path: path.clone(),
+							value: value.to_string(),
+						}),
+					}
+				});
+			}
+		},
+		_ => operations.push(Operation::Replace {
+			path: path.clone(),
+			value: current.to_string(),
+		}),
+	}
+}
+
+/// Appends `segment` to `path` (escaped as per RFC 6901) for the duration of `f`.
+fn with_segment(path: &mut String, segment: &str, f: impl FnOnce(&mut String)) {
+	let length = path.len();
+
+	push_segment(path, segment);
+	f(path);
+	path.truncate(length);
+}
+
+fn push_segment(path: &mut String, segment: &str) {
+	path.push('/');
+	for c in segment.chars() {
+		match c {
+			'~' => path.push_str("~0"),
+			'/' => path.push_str("~1"),
+			c => path.push(c),
+		}
+	}
+}
+
+/// Returns the JSON Pointer of the value being written at the end of `data`, which must be a valid prefix of a JSON
+/// object.
+pub fn pointer(data: &[u8]) -> String {
+	enum Segment {
+		/// An object, and the key of the value being written (if it has been read yet).
+		Key(Option<String>),
+		Index(usize),
+	}
+
+	let mut segments = vec![];
+	let mut parser = Parser { data, position: 0 };
+
+	while let Some(character) = parser.peek() {
+		match character {
+			b'{' => segments.push(Segment::Key(None)),
+			b'[' => segments.push(Segment::Index(0)),
+			b'}' | b']' => drop(segments.pop()),
+			b',' => match segments.last_mut() {
+				Some(Segment::Key(key)) => *key = None,
+				Some(Segment::Index(index)) => *index += 1,
+				None => {},
+			},
+			b'"' => {
+				// an unterminated string is the value being written, so there's nothing left to read.
+				let Ok(string) = parser.string() else {
+					break;
+				};
+
+				if let Some(Segment::Key(key @ None)) = segments.last_mut() {
+					*key = Some(string);
+				}
+				continue;
+			},
+			_ => {},
+		}
+
+		parser.position += 1;
+	}
+
+	let mut path = String::new();
+	for segment in &segments {
+		match segment {
+			Segment::Key(Some(key)) => push_segment(&mut path, key),
+			Segment::Key(None) => break,
+			Segment::Index(index) => push_segment(&mut path, &index.to_string()),
+		}
+	}
+
+	path
+}
+
+fn write_string(f: &mut impl Write, value: &str) -> fmt::Result {
+	f.write_char('"')?;
+
+	for c in value.chars() {
+		match c {
+			'"' => f.write_str("\\\"")?,
+			'\\' => f.write_str("\\\\")?,
+			'\n' => f.write_str("\\n")?,
+			'\r' => f.write_str("\\r")?,
+			'\t' => f.write_str("\\t")?,
+			c if c < ' ' => write!(f, "\\u{:04x}", c as u32)?,
+			c => f.write_char(c)?,
+		}
+	}
+
+	f.write_char('"')
+}
+
+struct Parser<'a> {
+	data: &'a [u8],
+	position: usize,
+}
+
+impl Parser<'_> {
+	fn peek(&self) -> Option<u8> {
+		self.data.get(self.position).copied()
+	}
+
+	fn skip_whitespace(&mut self) {
+		while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+			self.position += 1;
+		}
+	}
+
+	fn expect(&mut self, character: u8) -> Result<(), ErrorKind> {
+		self.skip_whitespace();
+
+		if self.peek() == Some(character) {
+			self.position += 1;
+			Ok(())
+		} else {
+			Err(ErrorKind::Invalid)
+		}
+	}
+
+	/// Consumes `open`, then a list of `item`s separated by commas until `close`.
+	fn list(
+		&mut self,
+		open: u8,
+		close: u8,
+		mut item: impl FnMut(&mut Self) -> Result<(), ErrorKind>,
+	) -> Result<(), ErrorKind> {
+		self.expect(open)?;
+		self.skip_whitespace();
+
+		if self.peek() == Some(close) {
+			self.position += 1;
+			return Ok(());
+		}
+
+		loop {
+			item(self)?;
+			self.skip_whitespace();
+
+			match self.peek() {
+				Some(b',') => self.position += 1,
+				Some(c) if c == close => {
+					self.position += 1;
+					return Ok(());
+				},
+				_ => return Err(ErrorKind::Invalid),
+			}
+		}
+	}
+
+	fn value(&mut self) -> Result<Value, ErrorKind> {
+		self.skip_whitespace();
+
+		match self.peek().ok_or(ErrorKind::Invalid)? {
+			b'[' => {
+				let mut items = vec![];
+				self.list(b'[', b']', |parser| {
+					items.push(parser.value()?);
+					Ok(())
+				})?;
+
+				Ok(Value::Array(items))
+			},
+			b'{' => {
+				let mut entries = vec![];
+				self.list(b'{', b'}', |parser| {
+					parser.skip_whitespace();
+					let key = parser.string()?;
+					parser.expect(b':')?;
+					let value = parser.value()?;
+
+					// like `JSON.parse`, the last duplicate key wins but keeps the position of the first.
+					match entries.iter_mut().find(|(name, _)| *name == key) {
+						Some(entry) => entry.1 = value,
+						None => entries.push((key, value)),
+					}
+					Ok(())
+				})?;
+
+				Ok(Value::Object(entries))
+			},
+			_ => {
+				let start = self.position;
+
+				if self.peek() == Some(b'"') {
+					self.string()?;
+				} else {
+					while matches!(self.peek(), Some(c) if !matches!(c, b',' | b']' | b'}' | b' ' | b'\t' | b'\n' | b'\r'))
+					{
+						self.position += 1;
+					}
+				}
+
+				let raw = core::str::from_utf8(&self.data[start..self.position])
+					.map_err(|_| ErrorKind::Utf8)?;
+				Ok(Value::Scalar(raw.to_string()))
+			},
+		}
+	}
+
+	/// Consumes a string, returning its decoded contents.
+	fn string(&mut self) -> Result<String, ErrorKind> {
+		self.expect(b'"')?;
+
+		let mut value = Vec::new();
+		let mut surrogate: Option<u32> = None;
+
+		loop {
+			let character = self.peek().ok_or(ErrorKind::Invalid)?;
+			self.position += 1;
+
+			if character != b'\\' && surrogate.take().is_some() {
+				push_char(&mut value, char::REPLACEMENT_CHARACTER);
+			}
+
+			let code_point = match character {
+				b'"' => break,
+				b'\\' => {
+					let escape = self.peek().ok_or(ErrorKind::Invalid)?;
+					self.position += 1;
+
+					match escape {
+						b'b' => 0x08,
+						b'f' => 0x0c,
+						b'n' => u32::from(b'\n'),
+						b'r' => u32::from(b'\r'),
+						b't' => u32::from(b'\t'),
+						b'u' => {
+							let hex = self
+								.data
+								.get(self.position..self.position + 4)
+								.ok_or(ErrorKind::Invalid)?;
+							self.position += 4;
+
+							core::str::from_utf8(hex)
+								.ok()
+								.and_then(|hex| u32::from_str_radix(hex, 16).ok())
+								.ok_or(ErrorKind::Invalid)?
+						},
+						escape => u32::from(escape),
+					}
+				},
+				character => {
+					value.push(character);
+					continue;
+				},
+			};
+
+			let code_point = match (surrogate.take(), code_point) {
+				(Some(high), 0xDC00..=0xDFFF) => {
+					0x10000 + ((high - 0xD800) << 10) + (code_point - 0xDC00)
+				},
+				(high, code_point) => {
+					if high.is_some() {
+						push_char(&mut value, char::REPLACEMENT_CHARACTER);
+					}
+
+					if (0xD800..=0xDBFF).contains(&code_point) {
+						surrogate = Some(code_point);
+						continue;
+					}
+
+					code_point
+				},
+			};
+
+			push_char(
+				&mut value,
+				char::from_u32(code_point).unwrap_or(char::REPLACEMENT_CHARACTER),
+			);
+		}
+
+		String::from_utf8(value).map_err(|_| ErrorKind::Utf8)
+	}
+}
+
+fn push_char(value: &mut Vec<u8>, character: char) {
+	value.extend_from_slice(character.encode_utf8(&mut [0; 4]).as_bytes());
+}
@@ -0,0 +1,100 @@
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::verifier::{Completion, Tail};
+
+/// A single repair performed while completing a JSON object.
+///
+/// Offsets point into the completed output. Since truncation always happens first, they are also valid
+/// offsets into the input for everything up to the truncation point.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair {
+	/// An incomplete trailing value (or key) was discarded, removing `length` bytes starting at `offset`.
+	Truncated { offset: usize, length: usize },
+
+	/// An incomplete character (e.g. split across chunks) of `length` bytes was removed from the end of an
+	/// unterminated string, starting at `offset`. This is either part of a multi-byte UTF-8 sequence, or a
+	/// `\uXXXX` high surrogate without its pair.
+	TrimmedCharacter { offset: usize, length: usize },
+
+	/// An unterminated string was closed at `offset`.
+	ClosedString { offset: usize },
+
+	/// A partial literal starting at `offset` was completed (e.g. `tru` into `true`).
+	CompletedLiteral {
+		offset: usize,
+		partial: &'static str,
+		literal: &'static str,
+	},
+
+	/// The closing `tokens` of unterminated arrays and objects were appended at `offset`.
+	Closed { offset: usize, tokens: String },
+}
+
+/// The list of repairs needed to complete a JSON object, in the order they were applied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairReport {
+	repairs: Vec<Repair>,
+}
+
+impl RepairReport {
+	pub(crate) fn new(completion: &Completion, length: usize) -> Self {
+		let mut repairs = vec![];
+		let mut offset = length;
+
+		if let Some(until) = completion.until.filter(|until| *until < length) {
+			offset = until;
+			repairs.push(Repair::Truncated {
+				offset,
+				length: length - until,
+			});
+		}
+
+		if completion.partial_character > 0 {
+			offset -= completion.partial_character;
+			repairs.push(Repair::TrimmedCharacter {
+				offset,
+				length: completion.partial_character,
+			});
+		}
+
+		match completion.tail {
+			Tail::None => {},
+			Tail::String => repairs.push(Repair::ClosedString { offset }),
+			Tail::Literal { partial, literal } => repairs.push(Repair::CompletedLiteral {
+				partial,
+				literal,
+				offset: offset - partial.len(),
+			}),
+		}
+		offset += completion.tail.bytes().len();
+
+		if !completion.closers.is_empty() {
+			repairs.push(Repair::Closed {
+				offset,
+				tokens: String::from_utf8_lossy(&completion.closers).into_owned(),
+			});
+		}
+
+		Self { repairs }
+	}
+
+	/// Returns the repairs that were performed.
+	#[must_use]
+	pub fn repairs(&self) -> &[Repair] {
+		&self.repairs
+	}
+
+	/// Returns `true` if the JSON object was already complete.
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.repairs.is_empty()
+	}
+
+	/// Returns `true` if part of the input had to be discarded.
+	#[must_use]
+	pub fn truncated(&self) -> bool {
+		self.repairs
+			.iter()
+			.any(|repair| matches!(repair, Repair::Truncated { .. }))
+	}
+}
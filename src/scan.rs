@@ -0,0 +1,46 @@
+/// The number of bytes checked at once by [`string_run`].
+const WORD: usize = core::mem::size_of::<u64>();
+
+const ONES: u64 = u64::from_ne_bytes([0x01; WORD]);
+const HIGHS: u64 = u64::from_ne_bytes([0x80; WORD]);
+
+/// Returns a word with the high bit set in (at least) the first byte of `word` that is `"`, `\` or a control
+/// character, and in no byte before it.
+const fn special(word: u64) -> u64 {
+	let quote = word ^ (ONES * b'"' as u64);
+	let backslash = word ^ (ONES * b'\\' as u64);
+
+	// borrows can only produce false positives after a flagged byte, so the first flag is always exact.
+	(below(quote, 1) | below(backslash, 1) | below(word, 0x20)) & HIGHS
+}
+
+/// Flags the bytes of `word` that are below `n`, which must be at most 0x80.
+const fn below(word: u64, n: u8) -> u64 {
+	word.wrapping_sub(ONES * n as u64) & !word
+}
+
+/// Returns the length of the run of plain string contents at the start of `data`, which stops at the first `"`, `\`
+/// or control character.
+pub fn string_run(data: &[u8]) -> usize {
+	let mut length = 0;
+
+	let mut words = data.chunks_exact(WORD);
+	for chunk in &mut words {
+		let mut bytes = [0; WORD];
+		bytes.copy_from_slice(chunk);
+
+		let flags = special(u64::from_le_bytes(bytes));
+		if flags != 0 {
+			return length + flags.trailing_zeros() as usize / 8;
+		}
+
+		length += WORD;
+	}
+
+	let remainder = words.remainder();
+	length
+		+ remainder
+			.iter()
+			.position(|character| matches!(character, b'"' | b'\\' | 0..=0x1F))
+			.unwrap_or(remainder.len())
+}
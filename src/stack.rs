@@ -0,0 +1,86 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Storage for the arrays and objects a verifier is currently inside of.
+pub trait Stack<T>: Clone {
+	/// The items on the stack, from the outermost to the innermost.
+	fn as_slice(&self) -> &[T];
+
+	/// Pushes `item` onto the stack, returning `false` if there is no room left for it.
+	fn push(&mut self, item: T) -> bool;
+
+	fn pop(&mut self) -> Option<T>;
+
+	fn last_mut(&mut self) -> Option<&mut T>;
+
+	fn clear(&mut self);
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Clone> Stack<T> for Vec<T> {
+	fn as_slice(&self) -> &[T] {
+		self
+	}
+
+	fn push(&mut self, item: T) -> bool {
+		self.push(item);
+		true
+	}
+
+	fn pop(&mut self) -> Option<T> {
+		self.pop()
+	}
+
+	fn last_mut(&mut self) -> Option<&mut T> {
+		self.as_mut_slice().last_mut()
+	}
+
+	fn clear(&mut self) {
+		self.clear();
+	}
+}
+
+/// A fixed-capacity stack stored inline, which never allocates.
+#[derive(Debug, Clone, Copy)]
+pub struct ArrayStack<T, const N: usize> {
+	items: [T; N],
+	len: usize,
+}
+
+impl<T: Copy + Default, const N: usize> Default for ArrayStack<T, N> {
+	fn default() -> Self {
+		Self {
+			items: [T::default(); N],
+			len: 0,
+		}
+	}
+}
+
+impl<T: Copy, const N: usize> Stack<T> for ArrayStack<T, N> {
+	fn as_slice(&self) -> &[T] {
+		&self.items[..self.len]
+	}
+
+	fn push(&mut self, item: T) -> bool {
+		if self.len == N {
+			return false;
+		}
+
+		self.items[self.len] = item;
+		self.len += 1;
+		true
+	}
+
+	fn pop(&mut self) -> Option<T> {
+		self.len = self.len.checked_sub(1)?;
+		Some(self.items[self.len])
+	}
+
+	fn last_mut(&mut self) -> Option<&mut T> {
+		self.items[..self.len].last_mut()
+	}
+
+	fn clear(&mut self) {
+		self.len = 0;
+	}
+}
@@ -4,7 +4,7 @@
 // > the order of variants in the `Token` and `CharacterType` enums **must** be kept in sync with the state transition table - we directly
 // > cast `Token` and `CharacterType` variants into `usizes` to index into the transition table to find the next state transition.
 
-use crate::Error;
+use crate::ErrorKind;
 
 #[repr(usize)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -181,20 +181,20 @@ const CATEGORIES: [CharacterType; 128] = {
     ]
 };
 
-pub fn character_type(character: u8) -> Result<CharacterType, Error> {
+pub fn character_type(character: u8) -> Result<CharacterType, ErrorKind> {
 	debug_assert!(character < 128);
 
 	match CATEGORIES[character as usize] {
-		CharacterType::Error => Err(Error::Invalid),
+		CharacterType::Error => Err(ErrorKind::Invalid),
 		character_type => Ok(character_type),
 	}
 }
 
-pub fn transition(from: Token, ty: CharacterType) -> Result<Transition, Error> {
+pub fn transition(from: Token, ty: CharacterType) -> Result<Transition, ErrorKind> {
 	debug_assert!(ty != CharacterType::Error);
 
 	match TRANSITIONS[from as usize][ty as usize] {
-		Transition::Error => Err(Error::Invalid),
+		Transition::Error => Err(ErrorKind::Invalid),
 		transition => Ok(transition),
 	}
 }
@@ -0,0 +1,704 @@
+use alloc::vec::Vec;
+
+use crate::{
+	verifier::{Transaction, Verifier},
+	Dialect, ErrorKind,
+};
+
+const INFINITY: (&[u8], &[u8]) = (b"Infinity", b"null");
+const NAN: (&[u8], &[u8]) = (b"NaN", b"null");
+const TRUE: (&[u8], &[u8]) = (b"True", b"true");
+const FALSE: (&[u8], &[u8]) = (b"False", b"false");
+const NONE: (&[u8], &[u8]) = (b"None", b"null");
+
+/// Where a [`Translator`] writes strict JSON. Every byte is applied to the verifier before it's kept.
+pub struct Output<'a> {
+	data: &'a mut Vec<u8>,
+	verifier: &'a mut Verifier,
+	/// The length of `data` and the state of `verifier` before anything was written, to undo it on error.
+	length: usize,
+	transaction: Transaction,
+}
+
+impl<'a> Output<'a> {
+	pub fn new(data: &'a mut Vec<u8>, verifier: &'a mut Verifier) -> Self {
+		Self {
+			length: data.len(),
+			transaction: verifier.begin(),
+			data,
+			verifier,
+		}
+	}
+
+	fn push(&mut self, character: u8) -> Result<(), ErrorKind> {
+		self.verifier
+			.update_within(character, &mut self.transaction)?;
+		self.data.push(character);
+
+		Ok(())
+	}
+
+	fn extend(&mut self, characters: &[u8]) -> Result<(), ErrorKind> {
+		characters
+			.iter()
+			.try_for_each(|character| self.push(*character))
+	}
+
+	/// Throws away everything written so far.
+	fn abort(&mut self) {
+		self.verifier.abort(&mut self.transaction);
+		self.data.truncate(self.length);
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+	/// Between tokens, or inside a token that is passed through as is.
+	Outside,
+
+	/// After a `/`, which must start a comment.
+	Slash,
+
+	/// Inside a `//` comment, until the end of the line.
+	LineComment,
+
+	/// Inside a `/* */` comment, with whether the last byte was a `*`.
+	BlockComment { star: bool },
+
+	/// Inside a string delimited by `quote`.
+	String { quote: u8 },
+
+	/// After a `\` inside a string delimited by `quote`.
+	Escape { quote: u8 },
+
+	/// After an escaped `\r`, which may be followed by the `\n` of the same line continuation.
+	Continuation { quote: u8 },
+
+	/// Inside a `\U` escape (Python), with the number of hexadecimal digits read so far and their value.
+	CodePoint { quote: u8, digits: u8, value: u32 },
+
+	/// Inside an octal escape (Python), with the number of digits read so far and their value.
+	Octal { quote: u8, digits: u8, value: u16 },
+
+	/// Inside an unquoted object key.
+	Identifier,
+
+	/// Inside `word`, which is written as `replacement`, after `matched` of its bytes.
+	Word {
+		word: &'static [u8],
+		replacement: &'static [u8],
+		matched: usize,
+	},
+
+	/// After the `+` or `-` that starts a number.
+	Sign(u8),
+
+	/// After the `0` that starts a number, which may be hexadecimal.
+	Zero,
+
+	/// Inside a hexadecimal number, with whether it has no digits yet.
+	Hexadecimal { empty: bool },
+
+	/// Inside a decimal number.
+	Number,
+
+	/// After a decimal point, which must be followed by a digit in JSON.
+	Point,
+}
+
+/// Translates the input of a non-JSON [`Dialect`] into strict JSON, one byte at a time.
+///
+/// # Remarks
+///
+/// Anything that can't be decided yet (a `/` that may start a comment, a `,` that may be trailing, a `0` that may
+/// start a hexadecimal number, ...) is held back until the bytes after it settle it. If the input ends before then,
+/// it is dropped, just like an incomplete value at the end of strict JSON.
+#[derive(Debug, Clone)]
+pub struct Translator {
+	dialect: Dialect,
+	state: State,
+	/// Whether a `,` was seen but not written yet, since it's dropped if it turns out to be a trailing comma.
+	comma: bool,
+	/// The whitespace after a pending comma, which is written along with it.
+	whitespace: Vec<u8>,
+	/// The whitespace written by the last flush, kept until the byte that flushed it is accepted.
+	flushed: Vec<u8>,
+	/// The decimal digits of a hexadecimal number, least significant first.
+	digits: Vec<u8>,
+	/// Whether each open array or object was opened as a tuple, innermost last, so it's closed the same way.
+	tuples: Vec<bool>,
+}
+
+impl Translator {
+	pub const fn new(dialect: Dialect) -> Self {
+		Self {
+			dialect,
+			comma: false,
+			state: State::Outside,
+			digits: Vec::new(),
+			whitespace: Vec::new(),
+			flushed: Vec::new(),
+			tuples: Vec::new(),
+		}
+	}
+
+	/// Resets this translator to the start of the input.
+	pub fn reset(&mut self) {
+		self.comma = false;
+		self.state = State::Outside;
+		self.whitespace.clear();
+		self.tuples.clear();
+	}
+
+	/// Returns `true` if the contents of the current string are written as is, so they can be copied over in bulk.
+	pub const fn passes_strings(&self) -> bool {
+		matches!(self.state, State::String { quote: b'"' })
+	}
+
+	/// Translates `character`, writing any JSON it completes to `output`. If an error is returned, neither this
+	/// translator nor `output` are changed.
+	pub fn translate(&mut self, character: u8, output: &mut Output) -> Result<(), ErrorKind> {
+		let (state, comma, whitespace) = (self.state, self.comma, self.whitespace.len());
+
+		let result = self.step(character, output);
+		if result.is_err() {
+			output.abort();
+
+			// only a flush shrinks the pending whitespace, and it sets it aside first. The hexadecimal digits don't
+			// need to be restored, since they only change once nothing else can fail.
+			if self.whitespace.len() < whitespace {
+				core::mem::swap(&mut self.whitespace, &mut self.flushed);
+			}
+			self.whitespace.truncate(whitespace);
+			self.state = state;
+			self.comma = comma;
+		}
+
+		result
+	}
+
+	fn step(&mut self, character: u8, output: &mut Output) -> Result<(), ErrorKind> {
+		// states that end before `character` go back to `Outside`, which then handles it as usual.
+		loop {
+			let consumed = match self.state {
+				State::Outside => return self.outside(character, output),
+				State::Slash | State::LineComment | State::BlockComment { .. } => {
+					self.comment(character, output)?
+				},
+				State::String { .. } | State::Escape { .. } | State::Continuation { .. } => {
+					self.string(character, output)?
+				},
+				State::CodePoint { .. } | State::Octal { .. } => {
+					self.escape_digits(character, output)?
+				},
+				State::Identifier | State::Word { .. } => self.word(character, output)?,
+				State::Sign(_)
+				| State::Zero
+				| State::Hexadecimal { .. }
+				| State::Number
+				| State::Point => self.numeric(character, output)?,
+			};
+
+			if consumed {
+				return Ok(());
+			}
+		}
+	}
+
+	/// Skips `character` inside a comment, returning whether it belonged to the comment.
+	fn comment(&mut self, character: u8, output: &mut Output) -> Result<bool, ErrorKind> {
+		match self.state {
+			State::Slash => {
+				self.state = match character {
+					b'/' => State::LineComment,
+					b'*' => State::BlockComment { star: false },
+					_ => return Err(ErrorKind::Invalid),
+				};
+			},
+			State::LineComment if character == b'\n' => {
+				self.state = State::Outside;
+				return Ok(false);
+			},
+			State::BlockComment { star: true } if character == b'/' => {
+				// the comment still separates the tokens around it.
+				self.state = State::Outside;
+				self.whitespace(b' ', output)?;
+			},
+			State::BlockComment { .. } => {
+				self.state = State::BlockComment {
+					star: character == b'*',
+				};
+			},
+			_ => {},
+		}
+
+		Ok(true)
+	}
+
+	/// Translates `character` inside a string, returning whether it belonged to the string.
+	fn string(&mut self, character: u8, output: &mut Output) -> Result<bool, ErrorKind> {
+		match self.state {
+			State::String { quote } => match character {
+				b'\\' => self.state = State::Escape { quote },
+				_ if character == quote => {
+					self.state = State::Outside;
+					output.push(b'"')?;
+				},
+				b'"' => output.extend(b"\\\"")?,
+				_ => output.push(character)?,
+			},
+			State::Escape { quote } => {
+				self.state = State::String { quote };
+				let python = self.dialect.python_literals();
+
+				match character {
+					_ if !self.dialect.escapes() => output.extend(&[b'\\', character])?,
+					b'"' | b'\\' | b'b' | b'f' | b'n' | b'r' | b't' | b'u' => {
+						output.extend(&[b'\\', character])?;
+					},
+					b'\'' => output.push(b'\'')?,
+					b'v' => output.extend(b"\\u000b")?,
+					b'x' => output.extend(b"\\u00")?,
+					b'a' if python => output.extend(b"\\u0007")?,
+					b'U' if python => {
+						self.state = State::CodePoint {
+							quote,
+							digits: 0,
+							value: 0,
+						};
+					},
+					b'0'..=b'7' if python => {
+						self.state = State::Octal {
+							quote,
+							digits: 1,
+							value: u16::from(character - b'0'),
+						};
+					},
+					b'0' => output.extend(b"\\u0000")?,
+					b'\n' => {},
+					b'\r' => self.state = State::Continuation { quote },
+					// in JSON5, any other escaped character other than a digit stands for itself. Python keeps the
+					// backslash of the escapes it doesn't define instead, which can't be told apart from a typo.
+					_ if !python && !character.is_ascii_digit() => output.push(character)?,
+					_ => return Err(ErrorKind::Invalid),
+				}
+			},
+			State::Continuation { quote } => {
+				self.state = State::String { quote };
+				return Ok(character == b'\n');
+			},
+			_ => {},
+		}
+
+		Ok(true)
+	}
+
+	/// Translates `character` inside an escape made of digits, returning whether it belonged to the escape.
+	fn escape_digits(&mut self, character: u8, output: &mut Output) -> Result<bool, ErrorKind> {
+		match self.state {
+			State::CodePoint {
+				quote,
+				digits,
+				value,
+			} => {
+				let digit = char::from(character)
+					.to_digit(16)
+					.ok_or(ErrorKind::Invalid)?;
+				let value = value << 4 | digit;
+
+				if digits < 7 {
+					self.state = State::CodePoint {
+						quote,
+						digits: digits + 1,
+						value,
+					};
+					return Ok(true);
+				}
+
+				self.state = State::String { quote };
+				escape_code_point(value, output)?;
+			},
+			State::Octal {
+				quote,
+				digits,
+				value,
+			} => {
+				// an octal escape has up to three digits, so it ends after the third or at the first byte that isn't one.
+				let more = matches!(character, b'0'..=b'7');
+				let value = if more {
+					value * 8 + u16::from(character - b'0')
+				} else {
+					value
+				};
+
+				if more && digits < 2 {
+					self.state = State::Octal {
+						quote,
+						digits: digits + 1,
+						value,
+					};
+				} else {
+					self.state = State::String { quote };
+					escape(value, output)?;
+				}
+
+				return Ok(more);
+			},
+			_ => {},
+		}
+
+		Ok(true)
+	}
+
+	/// Translates `character` inside an unquoted key or a word, returning whether it belonged to it.
+	fn word(&mut self, character: u8, output: &mut Output) -> Result<bool, ErrorKind> {
+		match self.state {
+			State::Identifier => {
+				if is_identifier(character) {
+					output.push(character)?;
+				} else {
+					self.state = State::Outside;
+					output.push(b'"')?;
+					return Ok(false);
+				}
+			},
+			State::Word {
+				word,
+				replacement,
+				matched,
+			} => {
+				if word.get(matched) != Some(&character) {
+					return Err(ErrorKind::Invalid);
+				}
+
+				// the replacement is written as the word is matched, but its last byte is held back until the whole
+				// word is, so a truncated word still completes to the replacement.
+				let written = |matched: usize| {
+					if matched == word.len() {
+						replacement.len()
+					} else {
+						matched.min(replacement.len() - 1)
+					}
+				};
+
+				self.state = if matched + 1 == word.len() {
+					State::Outside
+				} else {
+					State::Word {
+						word,
+						replacement,
+						matched: matched + 1,
+					}
+				};
+
+				output.extend(&replacement[written(matched)..written(matched + 1)])?;
+			},
+			_ => {},
+		}
+
+		Ok(true)
+	}
+
+	/// Translates `character` inside a number, returning whether it belonged to the number.
+	fn numeric(&mut self, character: u8, output: &mut Output) -> Result<bool, ErrorKind> {
+		match self.state {
+			State::Sign(sign) => {
+				self.state = State::Outside;
+
+				match character {
+					// `Infinity` and `NaN` are written as `null` regardless of their sign.
+					b'I' | b'N' => return Ok(false),
+					b'0'..=b'9' | b'.' => {
+						if sign == b'-' {
+							output.push(b'-')?;
+						}
+
+						self.number(character, output)?;
+					},
+					_ => {
+						output.push(sign)?;
+						return Ok(false);
+					},
+				}
+			},
+			State::Zero => {
+				if !matches!(character, b'x' | b'X') {
+					self.state = State::Number;
+					output.push(b'0')?;
+					return Ok(false);
+				}
+
+				self.digits.clear();
+				self.state = State::Hexadecimal { empty: true };
+			},
+			State::Hexadecimal { empty } => {
+				if let Some(digit) = char::from(character).to_digit(16) {
+					self.hexadecimal_digit(digit);
+					self.state = State::Hexadecimal { empty: false };
+					return Ok(true);
+				}
+
+				if empty || character == b'.' {
+					return Err(ErrorKind::Invalid);
+				}
+
+				self.state = State::Outside;
+				if self.digits.is_empty() {
+					output.push(b'0')?;
+				}
+				for digit in self.digits.iter().rev() {
+					output.push(b'0' + digit)?;
+				}
+
+				return Ok(false);
+			},
+			State::Number => match character {
+				b'.' => {
+					self.state = State::Point;
+					output.push(b'.')?;
+				},
+				b'0'..=b'9' | b'e' | b'E' | b'+' | b'-' => output.push(character)?,
+				_ => {
+					self.state = State::Outside;
+					return Ok(false);
+				},
+			},
+			State::Point => {
+				self.state = State::Number;
+
+				if !character.is_ascii_digit() {
+					output.push(b'0')?;
+					return Ok(false);
+				}
+
+				output.push(character)?;
+			},
+			_ => {},
+		}
+
+		Ok(true)
+	}
+
+	fn outside(&mut self, character: u8, output: &mut Output) -> Result<(), ErrorKind> {
+		let dialect = self.dialect;
+
+		match character {
+			b' ' | b'\t' | b'\n' | b'\r' => return self.whitespace(character, output),
+			0x0B | 0x0C if dialect.extra_whitespace() => return self.whitespace(b' ', output),
+			b'/' if dialect.comments() => {
+				self.state = State::Slash;
+				return Ok(());
+			},
+			b',' if dialect.trailing_commas() => {
+				self.flush(output)?;
+				self.comma = true;
+				return Ok(());
+			},
+			b']' | b'}' => return self.close(character, output),
+			b')' if dialect.python_literals() => return self.close(character, output),
+			_ => self.flush(output)?,
+		}
+
+		match character {
+			b'"' => {
+				self.state = State::String { quote: b'"' };
+				output.push(b'"')
+			},
+			b'\'' if dialect.single_quotes() => {
+				self.state = State::String { quote: b'\'' };
+				output.push(b'"')
+			},
+			_ if dialect.identifiers()
+				&& output.verifier.expects_key()
+				&& is_identifier_start(character) =>
+			{
+				self.state = State::Identifier;
+				output.extend(&[b'"', character])
+			},
+			_ if dialect.numbers() && output.verifier.expects_value() => match character {
+				b'+' | b'-' => {
+					self.state = State::Sign(character);
+					Ok(())
+				},
+				b'I' => self.start_word(INFINITY, character, output),
+				b'N' => self.start_word(NAN, character, output),
+				_ => self.number(character, output),
+			},
+			b'(' if dialect.python_literals() => self.open(b'[', true, output),
+			b'[' | b'{' if dialect.python_literals() => self.open(character, false, output),
+			_ if dialect.python_literals() && output.verifier.expects_value() => match character {
+				b'T' => self.start_word(TRUE, character, output),
+				b'F' => self.start_word(FALSE, character, output),
+				b'N' => self.start_word(NONE, character, output),
+				_ => output.push(character),
+			},
+			_ => output.push(character),
+		}
+	}
+
+	/// Opens an array or object with `opener`, remembering whether it's a tuple.
+	fn open(&mut self, opener: u8, tuple: bool, output: &mut Output) -> Result<(), ErrorKind> {
+		output.push(opener)?;
+		self.tuples.push(tuple);
+
+		Ok(())
+	}
+
+	/// Closes an array or object with `closer`, dropping a trailing comma before it.
+	fn close(&mut self, closer: u8, output: &mut Output) -> Result<(), ErrorKind> {
+		// only a tuple is closed with `)`, and it can't be closed with anything else.
+		if self.dialect.python_literals() && self.tuples.last() != Some(&(closer == b')')) {
+			return Err(ErrorKind::Invalid);
+		}
+
+		self.comma = false;
+		self.flush(output)?;
+
+		output.push(if closer == b')' { b']' } else { closer })?;
+		self.tuples.pop();
+
+		Ok(())
+	}
+
+	/// Starts a number at `character` (after its sign), or writes `character` as is if it doesn't start one.
+	fn number(&mut self, character: u8, output: &mut Output) -> Result<(), ErrorKind> {
+		match character {
+			b'0' => {
+				self.state = State::Zero;
+				Ok(())
+			},
+			b'1'..=b'9' => {
+				self.state = State::Number;
+				output.push(character)
+			},
+			b'.' => {
+				self.state = State::Number;
+				output.extend(b"0.")
+			},
+			_ => output.push(character),
+		}
+	}
+
+	/// Starts matching `word` at its first byte, `character`.
+	fn start_word(
+		&mut self,
+		(word, replacement): (&'static [u8], &'static [u8]),
+		character: u8,
+		output: &mut Output,
+	) -> Result<(), ErrorKind> {
+		self.state = State::Word {
+			word,
+			replacement,
+			matched: 0,
+		};
+
+		self.step(character, output)
+	}
+
+	/// Writes whitespace, holding it back along with a pending comma.
+	fn whitespace(&mut self, character: u8, output: &mut Output) -> Result<(), ErrorKind> {
+		if self.comma {
+			self.whitespace.push(character);
+			Ok(())
+		} else {
+			output.push(character)
+		}
+	}
+
+	/// Writes the pending comma (if it wasn't dropped) and the whitespace after it.
+	fn flush(&mut self, output: &mut Output) -> Result<(), ErrorKind> {
+		if core::mem::take(&mut self.comma) {
+			output.push(b',')?;
+		}
+
+		output.extend(&self.whitespace)?;
+		if !self.whitespace.is_empty() {
+			core::mem::swap(&mut self.whitespace, &mut self.flushed);
+			self.whitespace.clear();
+		}
+
+		Ok(())
+	}
+
+	/// Adds a hexadecimal digit to the decimal digits of the current number.
+	fn hexadecimal_digit(&mut self, digit: u32) {
+		let mut carry = digit;
+		for decimal in &mut self.digits {
+			let value = u32::from(*decimal) * 16 + carry;
+			*decimal = (value % 10) as u8;
+			carry = value / 10;
+		}
+
+		while carry > 0 {
+			self.digits.push((carry % 10) as u8);
+			carry /= 10;
+		}
+	}
+}
+
+impl Dialect {
+	const fn comments(self) -> bool {
+		matches!(self, Self::Json5 | Self::Jsonc)
+	}
+
+	const fn trailing_commas(self) -> bool {
+		matches!(self, Self::Json5 | Self::Python)
+	}
+
+	const fn single_quotes(self) -> bool {
+		matches!(self, Self::Json5 | Self::Python)
+	}
+
+	const fn escapes(self) -> bool {
+		matches!(self, Self::Json5 | Self::Python)
+	}
+
+	const fn extra_whitespace(self) -> bool {
+		matches!(self, Self::Json5)
+	}
+
+	const fn identifiers(self) -> bool {
+		matches!(self, Self::Json5)
+	}
+
+	const fn numbers(self) -> bool {
+		matches!(self, Self::Json5)
+	}
+
+	/// `True`, `False` and `None`, and tuples (written as arrays).
+	const fn python_literals(self) -> bool {
+		matches!(self, Self::Python)
+	}
+}
+
+/// Writes `value` as `\uXXXX` escapes. JSON has no longer escapes, so code points outside the BMP are written as a
+/// surrogate pair, while lone surrogates are left for the verifier to judge.
+fn escape_code_point(value: u32, output: &mut Output) -> Result<(), ErrorKind> {
+	if let Ok(unit) = u16::try_from(value) {
+		return escape(unit, output);
+	}
+
+	let character = char::from_u32(value).ok_or(ErrorKind::Invalid)?;
+	character
+		.encode_utf16(&mut [0; 2])
+		.iter()
+		.try_for_each(|unit| escape(*unit, output))
+}
+
+/// Writes `unit` as a `\uXXXX` escape.
+fn escape(unit: u16, output: &mut Output) -> Result<(), ErrorKind> {
+	const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+	output.extend(b"\\u")?;
+	[12, 8, 4, 0]
+		.iter()
+		.try_for_each(|shift| output.push(DIGITS[usize::from(unit >> shift & 0xF)]))
+}
+
+const fn is_identifier_start(character: u8) -> bool {
+	character.is_ascii_alphabetic() || matches!(character, b'_' | b'$') || character >= 0x80
+}
+
+const fn is_identifier(character: u8) -> bool {
+	is_identifier_start(character) || character.is_ascii_digit()
+}
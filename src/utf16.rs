@@ -0,0 +1,165 @@
+use alloc::vec::Vec;
+
+use crate::builder::Source;
+
+/// The byte order of UTF-16 input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+	Little,
+	Big,
+}
+
+/// Transcodes UTF-16 input into UTF-8 that can be fed to a [`Builder`](crate::Builder), one chunk at a time.
+///
+/// # Remarks
+///
+/// Chunks don't need to line up with code units or surrogate pairs: a trailing odd byte or high surrogate is held
+/// back until the next chunk completes it, or until [`Utf16::finish`] ends the input. A byte order mark at the start
+/// of the input selects the byte order (and is dropped), otherwise the byte order given on construction is used.
+/// Unpaired surrogates and odd bytes are replaced with U+FFFD.
+///
+/// A `Utf16` can be passed to [`Builder::update`](crate::Builder::update) itself, which applies the UTF-8 from the
+/// last call to [`Utf16::transcode`] or [`Utf16::finish`].
+///
+/// # Example
+/// ```
+/// # use repair_json::{Builder, Utf16};
+/// let input: Vec<u8> = "\u{FEFF}{ \"crab\": \"🦀".encode_utf16().flat_map(u16::to_le_bytes).collect();
+///
+/// let mut utf16 = Utf16::new();
+/// let mut builder = Builder::new();
+/// for chunk in input.chunks(3) {
+///     utf16.transcode(chunk);
+///     builder.update(&utf16).unwrap();
+/// }
+///
+/// utf16.finish();
+/// builder.update(&utf16).unwrap();
+///
+/// assert_eq!(builder.completed_string().unwrap(), r#"{ "crab": "🦀"}"#);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Utf16 {
+	endianness: Endianness,
+	/// Whether a byte order mark may still appear, which is only the case at the start of the input.
+	detect: bool,
+	/// The first byte of a code unit split across chunks.
+	pending_byte: Option<u8>,
+	/// A high surrogate waiting for the low surrogate that completes it.
+	high_surrogate: Option<u16>,
+	output: Vec<u8>,
+}
+
+impl Utf16 {
+	/// Creates a transcoder for UTF-16LE input, unless it starts with a big-endian byte order mark.
+	#[must_use]
+	pub const fn new() -> Self {
+		Self::with_endianness(Endianness::Little)
+	}
+
+	/// Creates a transcoder for UTF-16 input in the given byte order, unless it starts with a byte order mark.
+	#[must_use]
+	pub const fn with_endianness(endianness: Endianness) -> Self {
+		Self {
+			endianness,
+			detect: true,
+			pending_byte: None,
+			high_surrogate: None,
+			output: Vec::new(),
+		}
+	}
+
+	/// Transcodes `chunk`, returning the UTF-8 for every character it completes.
+	pub fn transcode(&mut self, chunk: &[u8]) -> &[u8] {
+		self.output.clear();
+
+		let mut chunk = chunk;
+		if let Some(first) = self.pending_byte.take() {
+			let Some((second, rest)) = chunk.split_first() else {
+				self.pending_byte = Some(first);
+				return &self.output;
+			};
+
+			self.code_unit([first, *second]);
+			chunk = rest;
+		}
+
+		let mut units = chunk.chunks_exact(2);
+		for unit in &mut units {
+			self.code_unit([unit[0], unit[1]]);
+		}
+		self.pending_byte = units.remainder().first().copied();
+
+		&self.output
+	}
+
+	/// Ends the input, returning U+FFFD for a trailing odd byte or high surrogate that was never completed.
+	pub fn finish(&mut self) -> &[u8] {
+		self.output.clear();
+
+		if self.high_surrogate.take().is_some() {
+			self.push(char::REPLACEMENT_CHARACTER);
+		}
+		if self.pending_byte.take().is_some() {
+			self.push(char::REPLACEMENT_CHARACTER);
+		}
+
+		&self.output
+	}
+
+	fn code_unit(&mut self, bytes: [u8; 2]) {
+		if self.detect {
+			self.detect = false;
+
+			match bytes {
+				[0xFF, 0xFE] => return self.endianness = Endianness::Little,
+				[0xFE, 0xFF] => return self.endianness = Endianness::Big,
+				_ => {},
+			}
+		}
+
+		let unit = match self.endianness {
+			Endianness::Little => u16::from_le_bytes(bytes),
+			Endianness::Big => u16::from_be_bytes(bytes),
+		};
+
+		let character = match (self.high_surrogate.take(), unit) {
+			(Some(high), 0xDC00..=0xDFFF) => {
+				let code_point =
+					0x10000 + ((u32::from(high) - 0xD800) << 10) + (u32::from(unit) - 0xDC00);
+				char::from_u32(code_point).unwrap_or(char::REPLACEMENT_CHARACTER)
+			},
+			(high, unit) => {
+				if high.is_some() {
+					self.push(char::REPLACEMENT_CHARACTER);
+				}
+
+				if (0xD800..=0xDBFF).contains(&unit) {
+					self.high_surrogate = Some(unit);
+					return;
+				}
+
+				char::from_u32(u32::from(unit)).unwrap_or(char::REPLACEMENT_CHARACTER)
+			},
+		};
+
+		self.push(character);
+	}
+
+	fn push(&mut self, character: char) {
+		self.output
+			.extend_from_slice(character.encode_utf8(&mut [0; 4]).as_bytes());
+	}
+}
+
+impl Default for Utf16 {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Source for Utf16 {
+	fn stream(&self) -> &[u8] {
+		&self.output
+	}
+}
@@ -1,8 +1,203 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "alloc")]
+use crate::{scan, Builder};
 use crate::{
+	stack::{ArrayStack, Stack},
 	table::{self, ComplexToken, Token, Transition},
-	Builder, Error, Status,
+	ConfigError, Error, ErrorKind, Options, Status,
 };
 
+/// The value that was being written when a JSON object was completed.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tail {
+	/// There is no value to finish, or it is being discarded.
+	None,
+
+	/// An unterminated string, which needs a closing quote.
+	String,
+
+	/// A partial literal (e.g. `tru`), which needs the rest of `literal`.
+	Literal {
+		partial: &'static str,
+		literal: &'static str,
+	},
+}
+
+#[cfg(feature = "alloc")]
+impl Tail {
+	const fn literal(partial: &'static str, literal: &'static str) -> Self {
+		Self::Literal { partial, literal }
+	}
+
+	/// The bytes needed to finish the value.
+	pub(crate) fn bytes(self) -> &'static [u8] {
+		match self {
+			Self::None => b"",
+			Self::String => b"\"",
+			Self::Literal { partial, literal } => &literal.as_bytes()[partial.len()..],
+		}
+	}
+}
+
+/// The steps required to turn the bytes applied to a [`Verifier`] into a complete JSON object.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+	/// Where to truncate the input, discarding an incomplete trailing value.
+	pub(crate) until: Option<usize>,
+
+	/// The value to finish after truncating.
+	pub(crate) tail: Tail,
+
+	/// The length of an incomplete character at the end of an unterminated string (either part of a multi-byte UTF-8
+	/// sequence or a `\uXXXX` high surrogate without its pair), which must be removed for the string to be valid.
+	pub(crate) partial_character: usize,
+
+	/// The closing brackets and braces for every unterminated array and object.
+	pub(crate) closers: Vec<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl Completion {
+	/// Returns how many of the `length` bytes this completion was created from are kept.
+	pub(crate) fn end(&self, length: usize) -> usize {
+		self.until.map_or(length, |until| until.min(length)) - self.partial_character
+	}
+
+	/// Applies this completion to the bytes it was created from.
+	pub(crate) fn apply(&self, data: &mut Vec<u8>) {
+		data.truncate(self.end(data.len()));
+
+		data.extend_from_slice(self.tail.bytes());
+		data.extend_from_slice(&self.closers);
+	}
+}
+
+/// Progress through a multi-byte UTF-8 sequence inside a string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Utf8 {
+	/// The number of bytes of the current sequence received so far.
+	received: u8,
+	/// The number of continuation bytes still expected.
+	remaining: u8,
+	/// The range the next continuation byte must be in, which rules out overlongs, surrogates and code points
+	/// above U+10FFFF.
+	next: (u8, u8),
+}
+
+impl Utf8 {
+	const EMPTY: Self = Self {
+		received: 0,
+		remaining: 0,
+		next: (0x80, 0xBF),
+	};
+
+	/// Returns the state after receiving `byte`, or an error if it can't continue the current sequence.
+	const fn update(self, byte: u8) -> Result<Self, ErrorKind> {
+		if self.remaining > 0 {
+			if byte < self.next.0 || byte > self.next.1 {
+				return Err(ErrorKind::Utf8);
+			}
+
+			return Ok(if self.remaining == 1 {
+				Self::EMPTY
+			} else {
+				Self {
+					received: self.received + 1,
+					remaining: self.remaining - 1,
+					next: (0x80, 0xBF),
+				}
+			});
+		}
+
+		let (remaining, next) = match byte {
+			0x00..=0x7F => return Ok(Self::EMPTY),
+			0xC2..=0xDF => (1, (0x80, 0xBF)),
+			0xE0 => (2, (0xA0, 0xBF)),
+			0xED => (2, (0x80, 0x9F)),
+			0xE1..=0xEF => (2, (0x80, 0xBF)),
+			0xF0 => (3, (0x90, 0xBF)),
+			0xF1..=0xF3 => (3, (0x80, 0xBF)),
+			0xF4 => (3, (0x80, 0x8F)),
+			_ => return Err(ErrorKind::Utf8),
+		};
+
+		Ok(Self {
+			next,
+			remaining,
+			received: 1,
+		})
+	}
+
+	/// The number of bytes of an incomplete sequence at the end of the input.
+	#[cfg(feature = "alloc")]
+	const fn pending(self) -> usize {
+		if self.remaining > 0 {
+			self.received as usize
+		} else {
+			0
+		}
+	}
+}
+
+/// Progress through `\uXXXX` escapes inside a string, used to pair up UTF-16 surrogates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Unicode {
+	/// The code unit of the `\uXXXX` escape currently being read.
+	code_unit: u16,
+	/// Whether the string currently ends with a high surrogate escape, which must be followed by a low one.
+	high_surrogate: bool,
+}
+
+impl Unicode {
+	/// The length of a `\uXXXX` escape.
+	#[cfg(feature = "alloc")]
+	const ESCAPE_LENGTH: usize = 6;
+
+	/// Returns the state after receiving `character` in the `state` token, or an error if it results in an unpaired
+	/// surrogate and those are being rejected.
+	fn update(self, state: Token, character: u8, reject_unpaired: bool) -> Result<Self, ErrorKind> {
+		let digit = || {
+			char::from(character)
+				.to_digit(16)
+				.and_then(|digit| u16::try_from(digit).ok())
+				.unwrap_or(0)
+		};
+		let unpaired = |high_surrogate: bool| {
+			if high_surrogate && reject_unpaired {
+				Err(ErrorKind::Invalid)
+			} else {
+				Ok(Self::default())
+			}
+		};
+
+		match state {
+			Token::U1 | Token::U2 | Token::U3 => Ok(Self {
+				code_unit: self.code_unit << 4 | digit(),
+				..self
+			}),
+			Token::U4 => match self.code_unit << 4 | digit() {
+				0xD800..=0xDBFF => unpaired(self.high_surrogate).map(|_| Self {
+					code_unit: 0,
+					high_surrogate: true,
+				}),
+				0xDC00..=0xDFFF => unpaired(!self.high_surrogate),
+				_ => unpaired(self.high_surrogate),
+			},
+			Token::String if character == b'\\' => Ok(self),
+			Token::Escape if character == b'u' => Ok(Self {
+				code_unit: 0,
+				..self
+			}),
+			Token::String | Token::Escape => unpaired(self.high_surrogate),
+			_ => Ok(self),
+		}
+	}
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ValueType {
 	Key,
@@ -10,6 +205,87 @@ enum ValueType {
 	Object,
 }
 
+/// An array or object that hasn't been closed yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Frame {
+	/// What the array or object expects next. Objects alternate between `Key` and `Object` (a value).
+	state: ValueType,
+	/// The value of `last_ok` when the array or object was opened.
+	last_ok: usize,
+	/// The number of items (for arrays) or keys (for objects) started so far.
+	count: usize,
+}
+
+impl Default for Frame {
+	fn default() -> Self {
+		Self {
+			count: 0,
+			last_ok: 0,
+			state: ValueType::Array,
+		}
+	}
+}
+
+/// The limits from [`Options`] that are enforced on every update.
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+	depth: usize,
+	length: usize,
+	string_length: usize,
+	keys_per_object: usize,
+	array_length: usize,
+}
+
+impl From<&Options> for Limits {
+	fn from(options: &Options) -> Self {
+		Self {
+			depth: options.maximum_depth,
+			length: options.maximum_length,
+			string_length: options.maximum_string_length,
+			keys_per_object: options.maximum_keys_per_object,
+			array_length: options.maximum_array_length,
+		}
+	}
+}
+
+/// Whether `state` is inside of a string (including its escape sequences).
+const fn in_string(state: Token) -> bool {
+	matches!(
+		state,
+		Token::String | Token::Escape | Token::U1 | Token::U2 | Token::U3 | Token::U4
+	)
+}
+
+/// The state machine shared by [`Verifier`] and [`StackVerifier`], generic over where open arrays and objects are kept.
+#[derive(Debug, Clone)]
+struct Machine<S> {
+	limits: Limits,
+	state: Token,
+	stack: S,
+	length: usize,
+	last_ok: usize,
+	/// The number of bytes in the string currently being written, not counting its opening quote.
+	string_length: usize,
+	utf8: Utf8,
+	unicode: Unicode,
+	reject_unpaired_surrogates: bool,
+	allow_invalid_utf8: bool,
+}
+
+/// The state of a [`Machine`] before a speculative update, which is enough to undo it.
+#[derive(Debug, Clone)]
+struct Journal<J> {
+	state: Token,
+	length: usize,
+	last_ok: usize,
+	string_length: usize,
+	utf8: Utf8,
+	unicode: Unicode,
+	/// The frames from this depth up have been saved to `saved` (innermost first) before they could be changed.
+	base: usize,
+	saved: J,
+}
+
 /// A fast JSON syntax validator for UTF-8 sequences.
 ///
 /// # Remarks
@@ -40,89 +316,248 @@ enum ValueType {
 ///     }
 /// }
 /// ```
-#[derive(Debug)]
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
 pub struct Verifier {
-	maximum: usize,
-	state: Token,
-	nested_state: Vec<ValueType>,
-	stack: Vec<(ValueType, usize)>,
-	length: usize,
-	last_ok: usize,
+	machine: Machine<Vec<Frame>>,
 }
 
-impl Verifier {
-	/// Creates a new `Verifier` with the default maximum depth of [`std::usize::MAX`].
-	#[must_use]
-	pub fn new() -> Self {
-		Self::default()
-	}
-
-	/// Creates a new `Verifier` with the specified maximum depth.
-	///
-	/// # Panics
-	///
-	/// Panics if `maximum_depth` is `0`.
-	#[must_use]
-	pub fn with_maximum_depth(maximum_depth: usize) -> Self {
-		assert!(maximum_depth > 0);
-
+impl<S: Stack<Frame>> Machine<S> {
+	/// Creates a new state machine, assuming `options` has already been validated.
+	fn with_options(options: &Options, stack: S) -> Self {
 		Self {
+			stack,
 			length: 0,
 			last_ok: 0,
-			stack: vec![],
-			nested_state: vec![],
+			string_length: 0,
+			utf8: Utf8::EMPTY,
 			state: Token::Begin,
-			maximum: maximum_depth,
+			unicode: Unicode::default(),
+			limits: Limits::from(options),
+			reject_unpaired_surrogates: options.reject_unpaired_surrogates,
+			allow_invalid_utf8: options.allow_invalid_utf8,
 		}
 	}
 
-	#[must_use]
-	/// Returns the current length of this JSON object.
-	pub const fn len(&self) -> usize {
-		self.length
-	}
-
-	#[must_use]
-	/// Returns `true` if this JSON object is empty.
-	pub const fn is_empty(&self) -> bool {
-		self.length == 0
-	}
-
-	#[must_use]
-	/// Returns the current status of this JSON object.
-	pub fn status(&self) -> Status {
-		if self.state == Token::Ok && self.nested_state.is_empty() {
+	fn status(&self) -> Status {
+		if self.state == Token::Ok && self.stack.as_slice().is_empty() {
 			Status::Valid
 		} else {
 			Status::Continue
 		}
 	}
 
-	/// Resets this JSON object to its initial state.
-	pub fn reset(&mut self) {
+	fn reset(&mut self) {
 		self.length = 0;
 		self.last_ok = 0;
+		self.string_length = 0;
+		self.utf8 = Utf8::EMPTY;
 		self.state = Token::Begin;
+		self.unicode = Unicode::default();
 
 		self.stack.clear();
-		self.nested_state.clear();
 	}
 
-	/// Applies `character` to this JSON object.
-	///
-	/// # Remarks
-	///
-	/// If `character` would cause this JSON object to become invalid, this method returns an error, but keeps its state.
-	/// The next invocation of `update()` will operate as if the bad character had never been applied.
-	///
-	/// # Errors
-	///
-	/// Returns an error if `character` is part of a valid UTF-8 sequence or if
-	/// inserting `character` would cause this JSON object to become invalid.
-	pub fn update(&mut self, character: u8) -> Result<(), Error> {
-		// UTF-8 continuation.
-		if character >= 128 {
-			return self.state(self.state);
+	fn update(&mut self, character: u8) -> Result<(), ErrorKind> {
+		if self.length >= self.limits.length {
+			return Err(ErrorKind::TooLong);
+		}
+
+		let unicode =
+			self.unicode
+				.update(self.state, character, self.reject_unpaired_surrogates)?;
+		let string_length = self.string_length(character)?;
+		let element = self.begins_element(character)?;
+
+		// count the new item or key up front, since `apply` may open another array or object for it.
+		if element {
+			self.count(1);
+		}
+
+		if let Err(e) = self.apply(character) {
+			if element {
+				self.count(-1);
+			}
+
+			return Err(e);
+		}
+
+		self.unicode = unicode;
+		self.string_length = string_length;
+
+		Ok(())
+	}
+
+	/// Returns the length of the current string after receiving `character`, or an error if it would exceed the limit.
+	const fn string_length(&self, character: u8) -> Result<usize, ErrorKind> {
+		let content =
+			in_string(self.state) && !(matches!(self.state, Token::String) && character == b'"');
+
+		if !content {
+			Ok(0)
+		} else if self.string_length >= self.limits.string_length {
+			Err(ErrorKind::StringTooLong)
+		} else {
+			Ok(self.string_length + 1)
+		}
+	}
+
+	/// Returns whether `character` starts a new item of an array or key of an object, or an error if the array or
+	/// object can't have any more of them.
+	fn begins_element(&self, character: u8) -> Result<bool, ErrorKind> {
+		let Some(frame) = self.stack.as_slice().last() else {
+			return Ok(false);
+		};
+
+		let (limit, error) = match (self.state, frame.state) {
+			(Token::Array | Token::Value, ValueType::Array)
+				if character < 128
+					&& !matches!(character, b' ' | b'\t' | b'\n' | b'\r' | b']')
+					&& table::character_type(character)
+						.and_then(|ty| table::transition(self.state, ty))
+						.is_ok() =>
+			{
+				(self.limits.array_length, ErrorKind::ArrayTooLong)
+			},
+			(Token::Object | Token::Key, ValueType::Key) if character == b'"' => {
+				(self.limits.keys_per_object, ErrorKind::TooManyKeys)
+			},
+			_ => return Ok(false),
+		};
+
+		if frame.count < limit {
+			Ok(true)
+		} else {
+			Err(error)
+		}
+	}
+
+	fn count(&mut self, change: isize) {
+		if let Some(frame) = self.stack.last_mut() {
+			frame.count = frame.count.wrapping_add_signed(change);
+		}
+	}
+
+	/// Applies the plain string contents at the start of `data` in one step, returning how many bytes were applied.
+	#[cfg(feature = "alloc")]
+	fn skip_string(&mut self, data: &[u8]) -> usize {
+		// pending UTF-8 sequences and high surrogates need the next byte to be checked on its own.
+		if self.state != Token::String || self.utf8 != Utf8::EMPTY || self.unicode.high_surrogate {
+			return 0;
+		}
+
+		let limit =
+			(self.limits.length - self.length).min(self.limits.string_length - self.string_length);
+
+		let data = &data[..limit.min(data.len())];
+		let run = &data[..scan::string_run(data)];
+
+		// stop before an invalid or incomplete UTF-8 sequence, so it gets validated byte by byte. Invalid sequences
+		// that are kept anyway are passed over, so the rest of the run isn't scanned again after each one.
+		let mut length = 0;
+		loop {
+			match core::str::from_utf8(&run[length..]) {
+				Ok(rest) => break length += rest.len(),
+				Err(e) => {
+					length += e.valid_up_to();
+					match e.error_len() {
+						Some(invalid) if self.allow_invalid_utf8 => length += invalid,
+						_ => break,
+					}
+				},
+			}
+		}
+
+		self.length += length;
+		self.string_length += length;
+		length
+	}
+
+	/// Starts a journal for undoing the updates that follow, saving frames to `saved`.
+	fn journal<J: Stack<Frame>>(&self, saved: J) -> Journal<J> {
+		Journal {
+			saved,
+			state: self.state,
+			length: self.length,
+			last_ok: self.last_ok,
+			string_length: self.string_length,
+			utf8: self.utf8,
+			unicode: self.unicode,
+			base: self.stack.as_slice().len(),
+		}
+	}
+
+	/// Applies `character` so that it can be undone with `journal`.
+	fn speculate<J: Stack<Frame>>(
+		&mut self,
+		character: u8,
+		journal: &mut Journal<J>,
+	) -> Result<(), ErrorKind> {
+		// an update only changes (or closes) the innermost frame, so saving it before every update is enough. Frames
+		// opened since the journal was started are thrown away on restore, and the stack shrinks one frame at a time.
+		let depth = self.stack.as_slice().len();
+		if depth > 0 && depth == journal.base {
+			let saved = journal.saved.push(self.stack.as_slice()[depth - 1]);
+			debug_assert!(saved, "journals must have room for one frame per update");
+
+			journal.base -= 1;
+		}
+
+		self.update(character)
+	}
+
+	/// Undoes every update applied since `journal` was started, leaving it ready to be used again.
+	fn restore<J: Stack<Frame>>(&mut self, journal: &mut Journal<J>) {
+		while self.stack.as_slice().len() > journal.base {
+			self.stack.pop();
+		}
+
+		for frame in journal.saved.as_slice().iter().rev() {
+			self.stack.push(*frame);
+		}
+
+		self.state = journal.state;
+		self.length = journal.length;
+		self.last_ok = journal.last_ok;
+		self.string_length = journal.string_length;
+		self.utf8 = journal.utf8;
+		self.unicode = journal.unicode;
+
+		journal.saved.clear();
+		journal.base = self.stack.as_slice().len();
+	}
+
+	/// Returns whether every byte of `input` would be accepted, saving frames to `saved` to undo them afterwards.
+	fn accepts<J: Stack<Frame>>(&mut self, input: &[u8], saved: J) -> bool {
+		let mut journal = self.journal(saved);
+
+		let accepted = input
+			.iter()
+			.all(|character| self.speculate(*character, &mut journal).is_ok());
+
+		self.restore(&mut journal);
+		accepted
+	}
+
+	fn apply(&mut self, character: u8) -> Result<(), ErrorKind> {
+		// non-ASCII characters are only allowed inside strings, and must form valid UTF-8 sequences.
+		let mut utf8 = self.utf8;
+		if self.state == Token::String {
+			utf8 = match self.utf8.update(character) {
+				Ok(utf8) => utf8,
+				// keep the offending byte (to be replaced with U+FFFD later) and start over from it.
+				Err(_) if self.allow_invalid_utf8 => {
+					Utf8::EMPTY.update(character).unwrap_or(Utf8::EMPTY)
+				},
+				Err(e) => return Err(e),
+			};
+
+			if character >= 128 {
+				self.utf8 = utf8;
+				return self.state(self.state);
+			}
+		} else if character >= 128 {
+			return Err(ErrorKind::Invalid);
 		}
 
 		let character_type = table::character_type(character)?;
@@ -136,149 +571,157 @@ impl Verifier {
 			Transition::Complex(ty) => match ty {
 				ComplexToken::BraceEmptyClose => {
 					self.pop(ValueType::Key)?;
-					self.exit(ValueType::Object)?;
 					self.state(Token::Ok)
 				},
 				ComplexToken::BraceClose => {
 					self.pop(ValueType::Object)?;
-					self.exit(ValueType::Object)?;
 					self.state(Token::Ok)
 				},
 				ComplexToken::BracketClose => {
 					self.pop(ValueType::Array)?;
-					self.exit(ValueType::Array)?;
 					self.state(Token::Ok)
 				},
 				ComplexToken::BraceOpen => {
 					self.push(ValueType::Key)?;
-					self.enter(ValueType::Object)?;
 					self.state(Token::Object)
 				},
 				ComplexToken::BracketOpen => {
 					self.push(ValueType::Array)?;
-					self.enter(ValueType::Array)?;
 					self.state(Token::Array)
 				},
-				ComplexToken::Quote => match self.nested_state.last() {
+				ComplexToken::Quote => match self.current() {
 					Some(ValueType::Key) => self.state(Token::Colon),
 					Some(ValueType::Object | ValueType::Array) => self.state(Token::Ok),
-					_ => Err(Error::Invalid),
+					_ => Err(ErrorKind::Invalid),
 				},
-				ComplexToken::Comma => match self.nested_state.last() {
+				ComplexToken::Comma => match self.current() {
 					Some(ValueType::Object) => {
 						self.last_ok = self.length;
 						self.switch(ValueType::Object, ValueType::Key)?;
 						self.state(Token::Key)
 					},
 					Some(ValueType::Array) => self.state(Token::Value),
-					_ => Err(Error::Invalid),
+					_ => Err(ErrorKind::Invalid),
 				},
 				ComplexToken::Kolon => {
 					self.switch(ValueType::Key, ValueType::Object)?;
 					self.state(Token::Value)
 				},
 			},
-		}
+		}?;
+
+		self.utf8 = utf8;
+		Ok(())
 	}
 
-	pub(crate) fn complete(&self) -> (Option<usize>, Vec<u8>) {
-		let mut tokens = Vec::new();
+	#[cfg(feature = "alloc")]
+	fn complete(&self) -> Completion {
+		let mut tail = Tail::None;
 		let mut last_ok = None;
+		let mut partial_character = 0;
 
 		match self.state {
 			Token::Integer => {},
-			Token::NullNu => tokens.extend("ull".bytes()),
-			Token::NullNul => tokens.extend("ll".bytes()),
-			Token::NullNull => tokens.extend("l".bytes()),
-			Token::TrueTr => tokens.extend("rue".bytes()),
-			Token::TrueTru => tokens.extend("ue".bytes()),
-			Token::FalseFa => tokens.extend("alse".bytes()),
-			Token::FalseFal => tokens.extend("lse".bytes()),
-			Token::FalseFals => tokens.extend("se".bytes()),
-			Token::FalseFalse | Token::TrueTrue => tokens.push(b'e'),
+			Token::NullNu => tail = Tail::literal("n", "null"),
+			Token::NullNul => tail = Tail::literal("nu", "null"),
+			Token::NullNull => tail = Tail::literal("nul", "null"),
+			Token::TrueTr => tail = Tail::literal("t", "true"),
+			Token::TrueTru => tail = Tail::literal("tr", "true"),
+			Token::TrueTrue => tail = Tail::literal("tru", "true"),
+			Token::FalseFa => tail = Tail::literal("f", "false"),
+			Token::FalseFal => tail = Tail::literal("fa", "false"),
+			Token::FalseFals => tail = Tail::literal("fal", "false"),
+			Token::FalseFalse => tail = Tail::literal("fals", "false"),
 			Token::String => {
-				if self.nested_state.last() == Some(&ValueType::Key) {
+				if self.current() == Some(ValueType::Key) {
 					last_ok = Some(self.last_ok);
 				} else {
-					tokens.push(b'"');
+					tail = Tail::String;
+
+					// drop an incomplete multi-byte character or a dangling high surrogate, so the string stays valid.
+					partial_character = self.utf8.pending();
+					if self.unicode.high_surrogate {
+						partial_character += Unicode::ESCAPE_LENGTH;
+					}
 				}
 			},
 			_ => last_ok = Some(self.last_ok),
-		};
-
-		tokens.extend(
-			self.stack
-				.iter()
-				.filter(|(_, depth)| {
-					let Some(last_ok) = last_ok else {
-						return true;
-					};
-
-					last_ok == 0 || *depth < last_ok
-				})
-				.rev()
-				.filter_map(|(ty, _)| match ty {
-					ValueType::Key => None,
-					ValueType::Array => Some(b']'),
-					ValueType::Object => Some(b'}'),
-				}),
-		);
+		}
 
-		(last_ok, tokens)
-	}
+		// frames are opened in order, so their `last_ok` never decreases and the ones that were opened after the cut
+		// point are all at the top of the stack. Walking down from the top keeps this proportional to the suffix.
+		let closers = self
+			.stack
+			.as_slice()
+			.iter()
+			.rev()
+			.skip_while(|frame| {
+				last_ok.is_some_and(|last_ok| last_ok != 0 && frame.last_ok >= last_ok)
+			})
+			.map(|frame| match frame.state {
+				ValueType::Array => b']',
+				ValueType::Key | ValueType::Object => b'}',
+			})
+			.collect();
 
-	fn push(&mut self, ty: ValueType) -> Result<(), Error> {
-		if self.nested_state.len() < self.maximum {
-			self.nested_state.push(ty);
-			Ok(())
-		} else {
-			Err(Error::Exceeded)
+		Completion {
+			tail,
+			closers,
+			partial_character,
+			until: last_ok.map(|until| if until == 0 { 1 } else { until }),
 		}
 	}
 
-	fn enter(&mut self, ty: ValueType) -> Result<(), Error> {
-		if self.stack.len() < self.maximum && ValueType::Key != ty {
-			self.stack.push((ty, self.last_ok));
-			return Ok(());
-		}
-
-		Err(Error::Invalid)
+	/// What the innermost array or object expects next.
+	fn current(&self) -> Option<ValueType> {
+		self.stack.as_slice().last().map(|frame| frame.state)
 	}
 
-	fn exit(&mut self, ty: ValueType) -> Result<(), Error> {
-		let (pop_ty, _) = self.stack.pop().ok_or(Error::Invalid)?;
+	fn push(&mut self, state: ValueType) -> Result<(), ErrorKind> {
+		let frame = Frame {
+			state,
+			count: 0,
+			last_ok: self.last_ok,
+		};
 
-		if ty != pop_ty {
-			return Err(Error::Invalid);
+		if self.stack.as_slice().len() < self.limits.depth && self.stack.push(frame) {
+			Ok(())
+		} else {
+			Err(ErrorKind::Exceeded)
 		}
-
-		Ok(())
 	}
 
-	fn pop(&mut self, ty: ValueType) -> Result<(), Error> {
-		if self.nested_state.pop() == Some(ty) {
+	fn pop(&mut self, state: ValueType) -> Result<(), ErrorKind> {
+		// only pop once we know the state matches, so a failed update leaves the stack untouched.
+		if self.current() == Some(state) {
+			self.stack.pop();
 			Ok(())
 		} else {
-			Err(Error::Invalid)
+			Err(ErrorKind::Invalid)
 		}
 	}
 
-	fn switch(&mut self, from: ValueType, to: ValueType) -> Result<(), Error> {
-		self.pop(from)?;
-		self.push(to)?;
-		Ok(())
+	fn switch(&mut self, from: ValueType, to: ValueType) -> Result<(), ErrorKind> {
+		match self.stack.last_mut() {
+			Some(frame) if frame.state == from => {
+				frame.state = to;
+				Ok(())
+			},
+			_ => Err(ErrorKind::Invalid),
+		}
 	}
 
 	#[allow(clippy::unnecessary_wraps)]
-	fn state(&mut self, state: Token) -> Result<(), Error> {
+	fn state(&mut self, state: Token) -> Result<(), ErrorKind> {
 		self.length += 1;
 
 		if state == Token::Ok {
 			self.last_ok = self.length;
 		}
 
-		if (state == Token::Object && self.state == Token::Value)
-			|| (state == Token::Array && self.nested_state.last() == Some(&ValueType::Array))
+		// an object at the top level is kept too, even when whitespace comes before it.
+		if (state == Token::Object && matches!(self.state, Token::Value | Token::Begin))
+			|| (state == Token::Array && self.current() == Some(ValueType::Array))
 		{
 			self.last_ok = self.length;
 		}
@@ -289,12 +732,273 @@ impl Verifier {
 	}
 }
 
+#[cfg(feature = "alloc")]
+impl Verifier {
+	/// Creates a new `Verifier` with the default maximum depth of [`usize::MAX`].
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates a new `Verifier` with the specified maximum depth.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `maximum_depth` is `0`.
+	pub fn with_maximum_depth(maximum_depth: usize) -> Result<Self, ConfigError> {
+		Self::with_options(&Options {
+			maximum_depth,
+			..Default::default()
+		})
+	}
+
+	/// Creates a new `Verifier` with the specified options. Options that only apply to a [`Builder`] are ignored.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the options are invalid, as described in [`Options::validate`].
+	pub fn with_options(options: &Options) -> Result<Self, ConfigError> {
+		options.validate()?;
+
+		Ok(Self {
+			machine: Machine::with_options(options, Vec::new()),
+		})
+	}
+
+	#[must_use]
+	/// Returns the current length of this JSON object.
+	pub const fn len(&self) -> usize {
+		self.machine.length
+	}
+
+	#[must_use]
+	/// Returns `true` if this JSON object is empty.
+	pub const fn is_empty(&self) -> bool {
+		self.machine.length == 0
+	}
+
+	#[must_use]
+	/// Returns the current status of this JSON object.
+	pub fn status(&self) -> Status {
+		self.machine.status()
+	}
+
+	/// Resets this JSON object to its initial state.
+	pub fn reset(&mut self) {
+		self.machine.reset();
+	}
+
+	/// Applies `character` to this JSON object.
+	///
+	/// # Remarks
+	///
+	/// If `character` would cause this JSON object to become invalid, this method returns an error, but keeps its state.
+	/// The next invocation of `update()` will operate as if the bad character had never been applied.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `character` is not part of a valid UTF-8 sequence or if
+	/// inserting `character` would cause this JSON object to become invalid.
+	pub fn update(&mut self, character: u8) -> Result<(), Error> {
+		self.machine
+			.update(character)
+			.map_err(|kind| Error::at(kind, self.machine.length))
+	}
+
+	/// Returns `true` if applying every byte of `input` to this JSON object would keep it valid.
+	///
+	/// # Remarks
+	///
+	/// The bytes are applied in place and then undone, so the state of this `Verifier` is left untouched regardless of
+	/// the result. Only the open arrays and objects that `input` could change are saved (inline, for inputs of up to
+	/// 16 bytes), which makes it cheap to test many multi-byte candidates (e.g. vocabulary tokens) against the same
+	/// state. Use a [`Fork`] to test candidates that share a prefix.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use repair_json::Verifier;
+	/// let mut verifier = Verifier::new();
+	/// verifier.update(b'{').unwrap();
+	///
+	/// assert!(verifier.accepts(br#" "name":"#));
+	/// assert!(!verifier.accepts(b"]"));
+	/// assert_eq!(verifier.len(), 1);
+	/// ```
+	#[must_use]
+	pub fn accepts(&mut self, input: &[u8]) -> bool {
+		// every byte saves at most one frame, so short inputs never allocate.
+		if input.len() <= INLINE_JOURNAL {
+			self.machine
+				.accepts(input, ArrayStack::<Frame, INLINE_JOURNAL>::default())
+		} else {
+			self.machine.accepts(input, Vec::new())
+		}
+	}
+
+	/// Starts a speculative update of this `Verifier`, which is undone when the returned [`Fork`] is dropped.
+	#[must_use]
+	pub fn fork(&mut self) -> Fork<'_> {
+		Fork {
+			journal: self.machine.journal(Vec::new()),
+			verifier: self,
+		}
+	}
+
+	pub(crate) fn complete(&self) -> Completion {
+		self.machine.complete()
+	}
+
+	/// Returns `true` if the next token must be an object key (or the end of the object).
+	pub(crate) const fn expects_key(&self) -> bool {
+		matches!(self.machine.state, Token::Object | Token::Key)
+	}
+
+	/// Returns `true` if the next token must be a value (or the end of the array).
+	pub(crate) const fn expects_value(&self) -> bool {
+		matches!(
+			self.machine.state,
+			Token::Begin | Token::Value | Token::Array
+		)
+	}
+
+	/// Applies the plain string contents at the start of `data` in one step, returning how many bytes were applied.
+	pub(crate) fn skip_string(&mut self, data: &[u8]) -> usize {
+		self.machine.skip_string(data)
+	}
+
+	/// Starts a group of updates that can be undone together with [`Verifier::abort`].
+	pub(crate) fn begin(&self) -> Transaction {
+		Transaction(self.machine.journal(ArrayStack::default()))
+	}
+
+	/// Applies `character` as part of `transaction`.
+	pub(crate) fn update_within(
+		&mut self,
+		character: u8,
+		transaction: &mut Transaction,
+	) -> Result<(), ErrorKind> {
+		self.machine.speculate(character, &mut transaction.0)
+	}
+
+	/// Undoes every update applied as part of `transaction`.
+	pub(crate) fn abort(&mut self, transaction: &mut Transaction) {
+		self.machine.restore(&mut transaction.0);
+	}
+}
+
+/// The state of a [`Verifier`] before the bytes written for one translated byte, which are undone together if any of
+/// them is rejected.
+///
+/// A translated byte closes at most one array or object, so at most two frames (the one it starts in, and the one
+/// around it) are ever saved.
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct Transaction(Journal<ArrayStack<Frame, 2>>);
+
+/// The number of bytes [`Verifier::accepts`] can test without allocating.
+#[cfg(feature = "alloc")]
+const INLINE_JOURNAL: usize = 16;
+
+/// A speculative update of a [`Verifier`], created by [`Verifier::fork`], which is undone when it's dropped.
+///
+/// # Remarks
+///
+/// Bytes applied to a `Fork` update the `Verifier` in place, saving only the open arrays and objects they change, so
+/// nothing is cloned. [`Fork::restore`] undoes them but keeps that storage around, and [`Fork::accepts`] tests
+/// candidates against the current state of the fork, so candidates that share a prefix only apply it once.
+///
+/// # Example
+///
+/// ```
+/// # use repair_json::Verifier;
+/// let mut verifier = Verifier::new();
+/// verifier.update(b'[').unwrap();
+///
+/// let mut fork = verifier.fork();
+/// for character in br#"{ "name": "#.iter() {
+///     fork.update(*character).unwrap();
+/// }
+/// assert!(fork.accepts(br#""miguel" }"#));
+/// assert!(!fork.accepts(b"]"));
+///
+/// fork.restore();
+/// assert!(fork.accepts(b"]"));
+///
+/// drop(fork);
+/// assert_eq!(verifier.len(), 1);
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct Fork<'a> {
+	verifier: &'a mut Verifier,
+	journal: Journal<Vec<Frame>>,
+}
+
+#[cfg(feature = "alloc")]
+impl Fork<'_> {
+	#[must_use]
+	/// Returns the current length of the forked JSON object.
+	pub const fn len(&self) -> usize {
+		self.verifier.len()
+	}
+
+	#[must_use]
+	/// Returns `true` if the forked JSON object is empty.
+	pub const fn is_empty(&self) -> bool {
+		self.verifier.is_empty()
+	}
+
+	#[must_use]
+	/// Returns the current status of the forked JSON object.
+	pub fn status(&self) -> Status {
+		self.verifier.status()
+	}
+
+	/// Applies `character` to the forked JSON object, just like [`Verifier::update`].
+	///
+	/// # Errors
+	///
+	/// Returns an error if `character` is not part of a valid UTF-8 sequence or if
+	/// inserting `character` would cause this JSON object to become invalid.
+	pub fn update(&mut self, character: u8) -> Result<(), Error> {
+		let machine = &mut self.verifier.machine;
+
+		machine
+			.speculate(character, &mut self.journal)
+			.map_err(|kind| Error::at(kind, machine.length))
+	}
+
+	/// Returns `true` if applying every byte of `input` to the forked JSON object would keep it valid, just like
+	/// [`Verifier::accepts`].
+	#[must_use]
+	pub fn accepts(&mut self, input: &[u8]) -> bool {
+		self.verifier.accepts(input)
+	}
+
+	/// Undoes everything applied to this fork, returning the [`Verifier`] to the state it was forked from.
+	pub fn restore(&mut self) {
+		self.verifier.machine.restore(&mut self.journal);
+	}
+}
+
+#[cfg(feature = "alloc")]
+impl Drop for Fork<'_> {
+	fn drop(&mut self) {
+		self.restore();
+	}
+}
+
+#[cfg(feature = "alloc")]
 impl Default for Verifier {
 	fn default() -> Self {
-		Self::with_maximum_depth(std::usize::MAX)
+		Self {
+			machine: Machine::with_options(&Options::default(), Vec::new()),
+		}
 	}
 }
 
+#[cfg(feature = "alloc")]
 impl From<Verifier> for Builder {
 	fn from(verifier: Verifier) -> Self {
 		Self {
@@ -303,3 +1007,116 @@ impl From<Verifier> for Builder {
 		}
 	}
 }
+
+/// A [`Verifier`] that keeps its open arrays and objects in an inline array instead of on the heap.
+///
+/// # Remarks
+///
+/// Verifying input with a `StackVerifier` never allocates, which makes it suitable for hot paths, untrusted input and
+/// `no_std` targets without an allocator. Opening more than `DEPTH` nested arrays or objects returns
+/// [`ErrorKind::Exceeded`], just like exceeding the maximum depth of a [`Verifier`].
+///
+/// Every document needs room for at least one frame, so creating a `StackVerifier::<0>` fails to compile:
+///
+/// ```compile_fail
+/// # use repair_json::StackVerifier;
+/// let verifier = StackVerifier::<0>::new();
+/// ```
+///
+/// # Example
+///
+/// ```
+/// # use repair_json::{Error, StackVerifier, Status};
+/// let mut verifier = StackVerifier::<2>::new();
+///
+/// for char in br#"{ "list": [1, 2] }"# {
+///     verifier.update(*char).unwrap();
+/// }
+/// assert_eq!(verifier.status(), Status::Valid);
+///
+/// verifier.reset();
+/// assert_eq!(verifier.accepts(b"[["), true);
+/// assert_eq!(verifier.accepts(b"[[["), false);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StackVerifier<const DEPTH: usize> {
+	machine: Machine<ArrayStack<Frame, DEPTH>>,
+}
+
+impl<const DEPTH: usize> StackVerifier<DEPTH> {
+	/// Evaluated by every constructor, so that a `DEPTH` that can't hold a document is rejected at compile time.
+	const NOT_EMPTY: () = assert!(DEPTH > 0, "a StackVerifier needs a DEPTH of at least 1");
+
+	/// Creates a new `StackVerifier` that allows up to `DEPTH` nested arrays and objects.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates a new `StackVerifier` with the specified options. `options.maximum_depth` is capped at `DEPTH`, and
+	/// options that only apply to a [`Builder`](crate::Builder) are ignored.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the options are invalid, as described in [`Options::validate`].
+	pub fn with_options(options: &Options) -> Result<Self, ConfigError> {
+		let () = Self::NOT_EMPTY;
+		options.validate()?;
+
+		Ok(Self {
+			machine: Machine::with_options(options, ArrayStack::default()),
+		})
+	}
+
+	#[must_use]
+	/// Returns the current length of this JSON object.
+	pub const fn len(&self) -> usize {
+		self.machine.length
+	}
+
+	#[must_use]
+	/// Returns `true` if this JSON object is empty.
+	pub const fn is_empty(&self) -> bool {
+		self.machine.length == 0
+	}
+
+	#[must_use]
+	/// Returns the current status of this JSON object.
+	pub fn status(&self) -> Status {
+		self.machine.status()
+	}
+
+	/// Resets this JSON object to its initial state.
+	pub fn reset(&mut self) {
+		self.machine.reset();
+	}
+
+	/// Applies `character` to this JSON object, leaving its state untouched if an error is returned.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `character` is not part of a valid UTF-8 sequence, if it would open more than `DEPTH`
+	/// nested arrays or objects, or if it would otherwise cause this JSON object to become invalid.
+	pub fn update(&mut self, character: u8) -> Result<(), Error> {
+		self.machine
+			.update(character)
+			.map_err(|kind| Error::at(kind, self.machine.length))
+	}
+
+	/// Returns `true` if applying every byte of `input` to this JSON object would keep it valid, undoing it afterwards.
+	#[must_use]
+	pub fn accepts(&mut self, input: &[u8]) -> bool {
+		self.machine
+			.accepts(input, ArrayStack::<Frame, DEPTH>::default())
+	}
+}
+
+impl<const DEPTH: usize> Default for StackVerifier<DEPTH> {
+	fn default() -> Self {
+		let () = Self::NOT_EMPTY;
+
+		Self {
+			machine: Machine::with_options(&Options::default(), ArrayStack::default()),
+		}
+	}
+}